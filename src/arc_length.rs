@@ -0,0 +1,81 @@
+use crate::fill::subpaths;
+
+/// A straight piece of a flattened path with a length.
+struct Segment {
+    start: (f64, f64),
+    end: (f64, f64),
+    /// how far along the path it starts
+    distance: f64,
+}
+
+/// A flattened path measured along its length, so the point and direction at
+/// any distance from the start can be found. moving to a new subpath covers
+/// no distance, the subpaths follow on from each other
+pub struct ArcLength {
+    segments: Vec<Segment>,
+    length: f64,
+}
+
+impl ArcLength {
+    pub fn new(points: &[(f64, f64, bool)]) -> ArcLength {
+        let mut segments = Vec::new();
+        let mut length = 0.0;
+        for subpath in subpaths(points) {
+            for segment in subpath.windows(2) {
+                let (a, b) = (segment[0], segment[1]);
+                let segment_length = (b.0 - a.0).hypot(b.1 - a.1);
+                if segment_length > 0.0 {
+                    segments.push(Segment { start: a, end: b, distance: length });
+                    length += segment_length;
+                }
+            }
+        }
+        ArcLength { segments, length }
+    }
+
+    pub fn length(&self) -> f64 {
+        self.length
+    }
+
+    /// the point `distance` along the path and the unit vector of its direction
+    /// there, or `None` past either end
+    pub fn at(&self, distance: f64) -> Option<((f64, f64), (f64, f64))> {
+        if !(0.0..=self.length).contains(&distance) {
+            return None;
+        }
+        // the last segment that starts at or before the distance
+        let index = self.segments.partition_point(|segment| segment.distance <= distance).checked_sub(1)?;
+        let Segment { start: a, end: b, distance: start } = self.segments[index];
+        let segment_length = (b.0 - a.0).hypot(b.1 - a.1);
+        let direction = ((b.0 - a.0) / segment_length, (b.1 - a.1) / segment_length);
+        let along = (distance - start).min(segment_length);
+        Some(((a.0 + direction.0 * along, a.1 + direction.1 * along), direction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_are_found_by_distance_along_the_path() {
+        // the jump to the second subpath is not part of the length
+        let path = [
+            (0.0, 0.0, false),
+            (10.0, 0.0, true),
+            (10.0, 0.0, true),
+            (10.0, 10.0, true),
+            (50.0, 50.0, false),
+            (50.0, 45.0, true),
+        ];
+        let measure = ArcLength::new(&path);
+        assert_eq!(measure.length(), 25.0);
+        assert_eq!(measure.at(0.0), Some(((0.0, 0.0), (1.0, 0.0))));
+        assert_eq!(measure.at(12.5), Some(((10.0, 2.5), (0.0, 1.0))));
+        assert_eq!(measure.at(22.0), Some(((50.0, 48.0), (0.0, -1.0))));
+        assert_eq!(measure.at(25.0), Some(((50.0, 45.0), (0.0, -1.0))));
+        assert_eq!(measure.at(-1.0), None);
+        assert_eq!(measure.at(25.5), None);
+        assert_eq!(ArcLength::new(&[]).at(0.0), None);
+    }
+}
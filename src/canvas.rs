@@ -8,18 +8,26 @@ use base64::{engine::general_purpose, Engine as _};
 use itertools::Itertools;
 use ratatui::{
     backend::CrosstermBackend,
+    buffer::Buffer,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style},
     symbols::{self, DOT},
     text::Spans,
     widgets::{
         canvas::{Canvas, Line, Points},
-        Block, Borders, GraphType, Tabs,
+        Block, Borders, GraphType, Paragraph, Tabs, Widget, Wrap,
     },
     Frame, Terminal,
 };
 
-use crate::{draw::draw_svg, INDEX};
+use crate::{
+    clip::contains,
+    draw::{draw_svg, RenderOptions, TextMode},
+    error::SvgError,
+    style::{DominantBaseline, TextAnchor},
+    svg::{PreserveAspectRatio, SvgText, ViewBox},
+    INDEX,
+};
 /// A group of data points
 #[derive(Debug, Clone)]
 pub struct SvgDataset<'a> {
@@ -66,14 +74,57 @@ impl<'a> SvgDataset<'a> {
     }
 }
 
+/// the canvas bounds that fit the 100 by 100 view box canvas into `layout`
+/// the way `aspect_ratio` asks, given the height to width ratio of a terminal cell
+pub fn canvas_bounds(
+    view_box: ViewBox,
+    aspect_ratio: PreserveAspectRatio,
+    layout: Rect,
+    cell_aspect: f64,
+) -> ([f64; 2], [f64; 2]) {
+    let Some((x_align, y_align)) = aspect_ratio.align else {
+        return ([0.0, 100.0], [0.0, 100.0]);
+    };
+    if layout.width == 0 || layout.height == 0 {
+        return ([0.0, 100.0], [0.0, 100.0]);
+    }
+    // the size of the layout in cell widths
+    let width = layout.width as f64;
+    let height = layout.height as f64 * cell_aspect;
+    let x_fit = width / view_box.width;
+    let y_fit = height / view_box.height;
+    let scale = if aspect_ratio.slice {
+        x_fit.max(y_fit)
+    } else {
+        x_fit.min(y_fit)
+    };
+    // how much of the canvas is visible along each axis, more than 100 for meet
+    let x_len = width / scale / view_box.width * 100.0;
+    let y_len = height / scale / view_box.height * 100.0;
+    let x_min = -(x_len - 100.0) * x_align.factor();
+    // the canvas y axis points up, so aligning to the top keeps 100 at the top
+    let y_max = 100.0 + (y_len - 100.0) * y_align.factor();
+    ([x_min, x_min + x_len], [y_max - y_len, y_max])
+}
+
+/// the canvas units between braille dots across and down, two columns and
+/// four rows of dots to a cell
+pub fn dot_size(bounds: ([f64; 2], [f64; 2]), layout: Rect) -> (f64, f64) {
+    (
+        (bounds.0[1] - bounds.0[0]) / (layout.width.max(1) as f64 * 2.0),
+        (bounds.1[1] - bounds.1[0]) / (layout.height.max(1) as f64 * 4.0),
+    )
+}
+
 pub fn canvas_draw(
     frame: &mut Frame<CrosstermBackend<Stdout>>,
     layout: Rect,
     datasets: Vec<SvgDataset>,
+    bounds: ([f64; 2], [f64; 2]),
 ) {
     let canvas = Canvas::default()
-        .x_bounds([0.0, 100.0])
-        .y_bounds([0.0, 100.0])
+        .x_bounds(bounds.0)
+        .y_bounds(bounds.1)
         .marker(ratatui::symbols::Marker::Braille)
         .paint(|ctx| {
             for dataset in datasets.clone() {
@@ -104,10 +155,101 @@ pub fn canvas_draw(
     frame.render_widget(canvas, layout);
 }
 
+/// Characters laid over the canvas, each at a cell of the frame.
+struct TextLayer {
+    cells: Vec<(u16, u16, char, Style)>,
+}
+
+impl Widget for TextLayer {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for (x, y, c, style) in self.cells {
+            if area.x <= x && x < area.right() && area.y <= y && y < area.bottom() {
+                buf.get_mut(x, y).set_char(c).set_style(style);
+            }
+        }
+    }
+}
+
+/// the cell, relative to the top left of `layout`, and style of every character
+/// of `texts` that is visible. each chunk of runs is lined up by the `text-anchor`
+/// of its first run, and each character sits on the row its `dominant-baseline` asks
+fn text_cells(texts: &[SvgText], layout: Rect, bounds: ([f64; 2], [f64; 2])) -> Vec<(u16, u16, char, Style)> {
+    let (x_len, y_len) = (bounds.0[1] - bounds.0[0], bounds.1[1] - bounds.1[0]);
+    let (columns, rows) = (layout.width as f64, layout.height as f64);
+    let mut cells = Vec::new();
+    // the characters of the current chunk, at fractional columns and rows
+    let mut chunk: Vec<(f64, f64, char, &SvgText)> = Vec::new();
+    let mut pen = (0.0, 0.0);
+    let mut place = |chunk: &mut Vec<(f64, f64, char, &SvgText)>| {
+        let Some(first) = chunk.first() else {
+            return;
+        };
+        let width = chunk.last().map_or(0.0, |last| last.0 + 1.0) - first.0;
+        let offset = match first.3.style.text_anchor {
+            TextAnchor::Start => 0.0,
+            TextAnchor::Middle => -width / 2.0,
+            TextAnchor::End => -width,
+        };
+        for (column, row, c, text) in chunk.drain(..) {
+            let column = (column + offset).round();
+            // a character fills the row below its top edge
+            let row = match text.style.dominant_baseline {
+                DominantBaseline::Alphabetic => row.round() - 1.0,
+                DominantBaseline::Middle => row.floor(),
+                DominantBaseline::Hanging => row.round(),
+            };
+            if column < 0.0 || column >= columns || row < 0.0 || row >= rows {
+                continue;
+            }
+            // the centre of the cell on the canvas, which the viewports must contain
+            let center = (
+                bounds.0[0] + (column + 0.5) / columns * x_len,
+                bounds.1[1] - (row + 0.5) / rows * y_len,
+            );
+            if !text.clip.iter().all(|polygon| contains(polygon, center)) {
+                continue;
+            }
+            if let Some(style) = text.style.text_style() {
+                cells.push((column as u16, row as u16, c, style));
+            }
+        }
+    };
+    for text in texts {
+        if let Some((x, y)) = text.position {
+            place(&mut chunk);
+            pen = ((x - bounds.0[0]) / x_len * columns, (bounds.1[1] - y) / y_len * rows);
+        }
+        // the canvas y axis points up and rows count down
+        pen.0 += text.shift.0 / x_len * columns;
+        pen.1 -= text.shift.1 / y_len * rows;
+        for c in text.text.chars() {
+            chunk.push((pen.0, pen.1, c, text));
+            pen.0 += 1.0;
+        }
+    }
+    place(&mut chunk);
+    cells
+}
+
+/// draw `texts` as terminal characters on top of whatever is in `layout`
+pub fn text_draw(
+    frame: &mut Frame<CrosstermBackend<Stdout>>,
+    layout: Rect,
+    texts: &[SvgText],
+    bounds: ([f64; 2], [f64; 2]),
+) {
+    let cells = text_cells(texts, layout, bounds)
+        .into_iter()
+        .map(|(x, y, c, style)| (layout.x + x, layout.y + y, c, style))
+        .collect();
+    frame.render_widget(TextLayer { cells }, layout);
+}
+
 #[derive(Clone)]
 pub struct Svg {
     svgs: HashMap<String, Vec<String>>,
     files: HashMap<String, String>,
+    options: RenderOptions,
 }
 
 impl Default for Svg {
@@ -120,11 +262,12 @@ impl Svg {
         Svg {
             svgs: HashMap::new(),
             files: HashMap::new(),
+            options: RenderOptions::default(),
         }
     }
     pub fn run(&mut self, terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Svg {
 
-        self.check();
+        let checked = self.check();
         
         let mut tabs = Vec::new();
 
@@ -132,7 +275,7 @@ impl Svg {
             tabs.push(Spans::from(i.to_string()));
         }
 
-        self.draw(terminal, tabs).ok();
+        self.draw(terminal, tabs, checked.err()).ok();
 
         self.to_owned()
 
@@ -155,6 +298,26 @@ impl Svg {
         self
     }
 
+    /// set the height to width ratio of a terminal cell, 2.0 by default
+    pub fn cell_aspect(&mut self, ratio: f64) -> &Svg {
+        self.options.cell_aspect = ratio;
+        self
+    }
+
+    /// set how far a flattened curve may stray from the true curve, in braille dots.
+    /// 0.25 by default, larger values draw curves with fewer segments
+    pub fn tolerance(&mut self, dots: f64) -> &Svg {
+        self.options.tolerance = dots;
+        self
+    }
+
+    /// set whether text is drawn as terminal characters or traced in braille
+    /// dots with the stroke font, terminal characters by default
+    pub fn text_mode(&mut self, mode: TextMode) -> &Svg {
+        self.options.text_mode = mode;
+        self
+    }
+
     pub fn remove(&mut self, key: &str) -> &Svg {
         self.svgs.remove(key);
         self
@@ -167,70 +330,41 @@ impl Svg {
     pub fn as_hashmap(&self) -> &HashMap<String, Vec<String>> {
         &self.svgs
     }
-    pub fn check(&mut self) -> Svg {
+    pub fn check(&mut self) -> Result<Svg, SvgError> {
         for file in self.files.keys().sorted().cloned().collect::<Vec<String>>() {
-            let current_file = File::open(file.clone()).unwrap();
-            //open file as bytes and encode to base64
-            let mut reader = BufReader::new(current_file);
-            let mut contents = String::new();
-            reader.read_to_string(&mut contents).unwrap();
+            let contents = read_file(&file)?;
             let check = general_purpose::STANDARD_NO_PAD.encode(contents.as_bytes());
             //panic!("{strings:?}");
-            if check != *self.files.get(&file).unwrap() {
-                self.remove(&file);
-                let current_file = File::open(file.clone()).unwrap();
-                //open file as bytes and encode to base64
-                let mut reader = BufReader::new(current_file);
-                let mut contents = String::new();
-                reader.read_to_string(&mut contents).unwrap();
-                let mut strings = Vec::new();
-                let tags = ["<path", "<rect", "<line"];
-                for i in contents.split('\n') {
-                    for tag in tags {
-                        if i.starts_with(tag) {
-                            strings.push(i.to_string());
-                            break;
-                        }
-                    }
-                }
-                self.svgs
-                    .insert(file.strip_suffix(".svg").unwrap().to_string(), strings);
-                *self.files.get_mut(&file).unwrap() =
-                    general_purpose::STANDARD_NO_PAD.encode(contents.as_bytes());
+            if Some(&check) != self.files.get(&file) {
+                self.load(&file, &contents);
             } else {
                 //println!("same");
             }
         }
-        self.to_owned()
+        Ok(self.to_owned())
     }
-    pub fn initialize(&mut self) -> Svg { 
+    pub fn initialize(&mut self) -> Result<Svg, SvgError> {
         for file in self.files.keys().sorted().cloned().collect::<Vec<String>>() {
-            let current_file = File::open(file.clone()).unwrap();
-            //open file as bytes and encode to base64
-            let mut reader = BufReader::new(current_file);
-            let mut contents = String::new();
-            reader.read_to_string(&mut contents).unwrap();
-            let mut strings = Vec::new();
-            let tags = ["<path", "<rect", "<line"];
-            for i in contents.split('\n') {
-                for tag in tags {
-                    if i.starts_with(tag) {
-                        strings.push(i.to_string());
-                        break;
-                    }
-                }
-            }
-            self.svgs.insert(file.strip_suffix(".svg").unwrap().to_string(), strings);
-            *self.files.get_mut(&file).unwrap() =
-                general_purpose::STANDARD_NO_PAD.encode(contents.as_bytes());
+            let contents = read_file(&file)?;
+            self.load(&file, &contents);
         }
-        self.to_owned()
-    
+        Ok(self.to_owned())
+    }
+    /// store a whole document under its file name without the extension,
+    /// so its own `<svg>` root and view box are kept
+    fn load(&mut self, file: &str, contents: &str) {
+        let key = file.strip_suffix(".svg").unwrap_or(file);
+        self.svgs.insert(key.to_string(), vec![contents.to_string()]);
+        self.files.insert(
+            file.to_string(),
+            general_purpose::STANDARD_NO_PAD.encode(contents.as_bytes()),
+        );
     }
     pub fn draw(
         &mut self,
         terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
         tabs: Vec<Spans>,
+        error: Option<SvgError>,
     ) -> Result<(), io::Error> {
         let draw = terminal.draw(|frame| {
             let terminal_rect = frame.size();
@@ -261,13 +395,126 @@ impl Svg {
                 .load(std::sync::atomic::Ordering::Relaxed)
                 .try_into()
                 .unwrap();
-            draw_svg(
-                self.svgs.get(&keys[index]).unwrap().clone(),
-                frame,
-                chunks[0],
-            );
+            // the area inside the border and below the tabs
+            let pane = Layout::default()
+                .margin(1)
+                .constraints([Constraint::Length(1), Constraint::Min(0)].as_ref())
+                .split(chunks[0])[1];
+            let drawn = match error {
+                Some(error) => Err(error),
+                None => draw_svg(
+                    self.svgs.get(&keys[index]).unwrap().clone(),
+                    frame,
+                    pane,
+                    &self.options,
+                ),
+            };
+            if let Err(error) = drawn {
+                // show the error instead of the drawing
+                let message = Paragraph::new(error.to_string())
+                    .style(Style::default().fg(Color::Red))
+                    .wrap(Wrap { trim: true });
+                frame.render_widget(message, pane);
+            }
         });
         drop(draw);
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// read a watched file to a string
+fn read_file(file: &str) -> Result<String, SvgError> {
+    let current_file = File::open(file)?;
+    //open file as bytes and encode to base64
+    let mut reader = BufReader::new(current_file);
+    let mut contents = String::new();
+    reader.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::Style as SvgStyle;
+    use crate::transform::Transform;
+
+    fn text(text: &str, position: Option<(f64, f64)>, declarations: &str) -> SvgText {
+        let mut style = SvgStyle::default();
+        style.set_declarations(declarations);
+        SvgText {
+            text: text.to_string(),
+            position,
+            shift: (0.0, 0.0),
+            style,
+            clip: Vec::new(),
+            transform: Transform::identity(),
+        }
+    }
+
+    /// the column, row and character of every cell
+    fn placed(texts: &[SvgText]) -> Vec<(u16, u16, char)> {
+        // five canvas units to a column and ten to a row
+        text_cells(texts, Rect::new(0, 0, 20, 10), ([0.0, 100.0], [0.0, 100.0]))
+            .into_iter()
+            .map(|(x, y, c, _)| (x, y, c))
+            .collect()
+    }
+
+    #[test]
+    fn text_is_anchored_to_its_chunk() {
+        let start = [text("ab", Some((50.0, 50.0)), ""), text("c", None, "text-anchor: end")];
+        assert_eq!(placed(&start), vec![(10, 4, 'a'), (11, 4, 'b'), (12, 4, 'c')]);
+        let middle = [text("ab", Some((50.0, 50.0)), "text-anchor: middle"), text("cd", None, "")];
+        assert_eq!(placed(&middle), vec![(8, 4, 'a'), (9, 4, 'b'), (10, 4, 'c'), (11, 4, 'd')]);
+        let end = [text("ab", Some((50.0, 50.0)), "text-anchor: end; dominant-baseline: hanging")];
+        assert_eq!(placed(&end), vec![(8, 5, 'a'), (9, 5, 'b')]);
+    }
+
+    #[test]
+    fn hidden_text_is_dropped() {
+        let mut clipped = text("abcd", Some((40.0, 50.0)), "");
+        clipped.clip.push(vec![(0.0, 0.0), (50.0, 0.0), (50.0, 100.0), (0.0, 100.0)]);
+        assert_eq!(placed(&[clipped]), vec![(8, 4, 'a'), (9, 4, 'b')]);
+        // off the edge of the layout, or with nothing to paint it with
+        assert_eq!(placed(&[text("abc", Some((95.0, 50.0)), "")]), vec![(19, 4, 'a')]);
+        assert!(placed(&[text("abc", Some((0.0, 50.0)), "fill: none")]).is_empty());
+    }
+
+    #[test]
+    fn view_box_fits_the_layout() {
+        use crate::svg::Align::{Max, Mid, Min};
+        // 40 by 10 cells twice as tall as they are wide are 40 by 20 cell widths,
+        // so a square view box meets with room either side and slices its middle half
+        let layout = Rect::new(0, 0, 40, 10);
+        let table = [
+            ((Min, Min), ([0.0, 200.0], [0.0, 100.0]), ([0.0, 100.0], [50.0, 100.0])),
+            ((Mid, Min), ([-50.0, 150.0], [0.0, 100.0]), ([0.0, 100.0], [50.0, 100.0])),
+            ((Max, Min), ([-100.0, 100.0], [0.0, 100.0]), ([0.0, 100.0], [50.0, 100.0])),
+            ((Min, Mid), ([0.0, 200.0], [0.0, 100.0]), ([0.0, 100.0], [25.0, 75.0])),
+            ((Mid, Mid), ([-50.0, 150.0], [0.0, 100.0]), ([0.0, 100.0], [25.0, 75.0])),
+            ((Max, Mid), ([-100.0, 100.0], [0.0, 100.0]), ([0.0, 100.0], [25.0, 75.0])),
+            ((Min, Max), ([0.0, 200.0], [0.0, 100.0]), ([0.0, 100.0], [0.0, 50.0])),
+            ((Mid, Max), ([-50.0, 150.0], [0.0, 100.0]), ([0.0, 100.0], [0.0, 50.0])),
+            ((Max, Max), ([-100.0, 100.0], [0.0, 100.0]), ([0.0, 100.0], [0.0, 50.0])),
+        ];
+        for (align, meet, slice) in table {
+            for (bounds, slice) in [(meet, false), (slice, true)] {
+                let aspect_ratio = PreserveAspectRatio { align: Some(align), slice };
+                assert_eq!(canvas_bounds(ViewBox::default(), aspect_ratio, layout, 2.0), bounds, "{align:?} {slice}");
+            }
+        }
+
+        let mid = PreserveAspectRatio::default();
+        let none = PreserveAspectRatio { align: None, slice: false };
+        let whole = ([0.0, 100.0], [0.0, 100.0]);
+        assert_eq!(canvas_bounds(ViewBox::default(), none, layout, 2.0), whole);
+        assert_eq!(canvas_bounds(ViewBox::default(), mid, Rect::new(0, 0, 0, 10), 2.0), whole);
+        // 20 by 10 cells are square only when a cell is twice as tall as it is wide
+        let square = Rect::new(0, 0, 20, 10);
+        assert_eq!(canvas_bounds(ViewBox::default(), mid, square, 2.0), whole);
+        assert_eq!(canvas_bounds(ViewBox::default(), mid, square, 1.0), ([-50.0, 150.0], [0.0, 100.0]));
+        // a view box twice as wide as it is tall fills that
+        let wide = ViewBox { width: 200.0, height: 100.0, ..ViewBox::default() };
+        assert_eq!(canvas_bounds(wide, mid, square, 1.0), whole);
+    }
+}
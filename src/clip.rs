@@ -0,0 +1,107 @@
+/// a point on every edge of a convex polygon and the normal pointing inside,
+/// none at all for a polygon with no area
+fn inward_edges(polygon: &[(f64, f64)]) -> Vec<((f64, f64), (f64, f64))> {
+    let area = polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+        .sum::<f64>();
+    if polygon.len() < 3 || area == 0.0 {
+        return Vec::new();
+    }
+    // the inward normal of an edge is on its left when the polygon winds counterclockwise
+    let winding = area.signum();
+    polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| (*a, (-(b.1 - a.1) * winding, (b.0 - a.0) * winding)))
+        .collect()
+}
+
+/// is `point` inside the convex `polygon`, or on its edge
+pub fn contains(polygon: &[(f64, f64)], point: (f64, f64)) -> bool {
+    let edges = inward_edges(polygon);
+    !edges.is_empty()
+        && edges
+            .iter()
+            .all(|(start, normal)| normal.0 * (point.0 - start.0) + normal.1 * (point.1 - start.1) >= 0.0)
+}
+
+/// the parts of every drawn segment of `points` inside the convex `polygon`.
+/// a segment that leaves and comes back starts again with the pen up, so
+/// spans from a fill and polylines from a stroke can both be clipped
+pub fn clip(points: &[(f64, f64, bool)], polygon: &[(f64, f64)]) -> Vec<(f64, f64, bool)> {
+    let edges = inward_edges(polygon);
+    // an empty viewport hides everything
+    if edges.is_empty() {
+        return Vec::new();
+    }
+
+    let mut clipped: Vec<(f64, f64, bool)> = Vec::new();
+    for segment in points.windows(2).filter(|segment| segment[1].2) {
+        let (start, end) = ((segment[0].0, segment[0].1), (segment[1].0, segment[1].1));
+        let direction = (end.0 - start.0, end.1 - start.1);
+        // Cyrus-Beck, narrow the part of the segment inside every edge
+        let (mut enter, mut exit) = (0.0_f64, 1.0_f64);
+        for (point, normal) in &edges {
+            let inside = normal.0 * (start.0 - point.0) + normal.1 * (start.1 - point.1);
+            let towards = normal.0 * direction.0 + normal.1 * direction.1;
+            if towards == 0.0 {
+                if inside < 0.0 {
+                    exit = -1.0;
+                }
+            } else if towards > 0.0 {
+                enter = enter.max(-inside / towards);
+            } else {
+                exit = exit.min(-inside / towards);
+            }
+        }
+        if enter > exit {
+            continue;
+        }
+        let from = (start.0 + direction.0 * enter, start.1 + direction.1 * enter);
+        let to = (start.0 + direction.0 * exit, start.1 + direction.1 * exit);
+        // carry on from the last segment when it ended where this one starts
+        if clipped.last().map(|last| (last.0, last.1)) != Some(from) {
+            clipped.push((from.0, from.1, false));
+        }
+        clipped.push((to.0, to.1, true));
+    }
+    clipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE: [(f64, f64); 4] = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+
+    #[test]
+    fn segments_are_cut_at_the_edges() {
+        let line = [(-5.0, 5.0, false), (5.0, 5.0, true), (15.0, 5.0, true)];
+        assert_eq!(clip(&line, &SQUARE), vec![(0.0, 5.0, false), (5.0, 5.0, true), (10.0, 5.0, true)]);
+        // the winding of the polygon does not matter
+        let mut reversed = SQUARE;
+        reversed.reverse();
+        assert_eq!(clip(&line, &reversed), clip(&line, &SQUARE));
+    }
+
+    #[test]
+    fn leaving_and_coming_back_lifts_the_pen() {
+        let zigzag = [(5.0, 5.0, true), (5.0, 15.0, true), (8.0, 15.0, true), (8.0, 5.0, true)];
+        assert_eq!(
+            clip(&zigzag, &SQUARE),
+            vec![(5.0, 5.0, false), (5.0, 10.0, true), (8.0, 10.0, false), (8.0, 5.0, true)]
+        );
+        // spans outside are dropped, and so is everything in an empty polygon
+        assert!(clip(&[(20.0, 5.0, false), (30.0, 5.0, true)], &SQUARE).is_empty());
+        assert!(clip(&zigzag, &[(0.0, 0.0), (10.0, 0.0), (20.0, 0.0)]).is_empty());
+    }
+
+    #[test]
+    fn points_inside() {
+        assert!(contains(&SQUARE, (5.0, 5.0)));
+        assert!(contains(&SQUARE, (10.0, 5.0)));
+        assert!(!contains(&SQUARE, (10.5, 5.0)));
+    }
+}
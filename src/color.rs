@@ -0,0 +1,350 @@
+use ratatui::style::Color;
+
+/// A CSS colour in sRGB with an alpha between 0 (transparent) and 1 (opaque).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgba {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: f64,
+}
+
+impl Rgba {
+    pub fn new(r: u8, g: u8, b: u8, a: f64) -> Rgba {
+        Rgba { r, g, b, a }
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.a <= 0.0
+    }
+
+    /// parse a hex, `rgb()`, `rgba()`, `hsl()`, `hsla()`, named or `transparent` colour.
+    /// `currentColor` depends on the element, so it is left to the caller
+    pub fn parse(value: &str) -> Option<Rgba> {
+        let value = value.trim();
+        if let Some(hex) = value.strip_prefix('#') {
+            return parse_hex(hex);
+        }
+        if let Some((name, args)) = value.split_once('(') {
+            let args = args.strip_suffix(')')?;
+            return match name.trim().to_ascii_lowercase().as_str() {
+                "rgb" | "rgba" => parse_rgb(args),
+                "hsl" | "hsla" => parse_hsl(args),
+                _ => None,
+            };
+        }
+        if value.eq_ignore_ascii_case("transparent") {
+            return Some(Rgba::new(0, 0, 0, 0.0));
+        }
+        NAMED_COLORS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(value))
+            .map(|(_, [r, g, b])| Rgba::new(*r, *g, *b, 1.0))
+    }
+}
+
+/// the terminal has no alpha, so the colour is drawn opaque
+impl From<Rgba> for Color {
+    fn from(color: Rgba) -> Self {
+        Color::Rgb(color.r, color.g, color.b)
+    }
+}
+
+/// `rgb`, `rgba`, `rrggbb` or `rrggbbaa`
+fn parse_hex(hex: &str) -> Option<Rgba> {
+    if !hex.is_ascii() || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digits = match hex.len() {
+        // each short digit is doubled, so `f` becomes `ff`
+        3 | 4 => hex
+            .chars()
+            .map(|c| u8::from_str_radix(&c.to_string().repeat(2), 16).ok())
+            .collect::<Option<Vec<u8>>>()?,
+        6 | 8 => (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+            .collect::<Option<Vec<u8>>>()?,
+        _ => return None,
+    };
+    let alpha = digits.get(3).map_or(1.0, |a| *a as f64 / 255.0);
+    Some(Rgba::new(digits[0], digits[1], digits[2], alpha))
+}
+
+/// the arguments of a colour function in either the comma separated form,
+/// `255, 0, 0, 0.5`, or the space separated form, `255 0 0 / 50%`
+fn arguments(args: &str) -> Vec<&str> {
+    args.split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+        .filter(|arg| !arg.is_empty())
+        .collect()
+}
+
+/// a number, or a percentage of `full`
+fn number_or_percentage(value: &str, full: f64) -> Option<f64> {
+    match value.strip_suffix('%') {
+        Some(percentage) => Some(percentage.parse::<f64>().ok()? / 100.0 * full),
+        None => value.parse::<f64>().ok(),
+    }
+}
+
+fn alpha(args: &[&str]) -> Option<f64> {
+    match args.get(3) {
+        Some(alpha) => Some(number_or_percentage(alpha, 1.0)?.clamp(0.0, 1.0)),
+        None => Some(1.0),
+    }
+}
+
+fn channel(value: f64) -> u8 {
+    value.round().clamp(0.0, 255.0) as u8
+}
+
+fn parse_rgb(args: &str) -> Option<Rgba> {
+    let args = arguments(args);
+    if args.len() != 3 && args.len() != 4 {
+        return None;
+    }
+    let mut channels = [0u8; 3];
+    for (channel_value, arg) in channels.iter_mut().zip(&args) {
+        *channel_value = channel(number_or_percentage(arg, 255.0)?);
+    }
+    Some(Rgba::new(channels[0], channels[1], channels[2], alpha(&args)?))
+}
+
+/// a hue in degrees, which may be given in `deg`, `grad`, `rad` or `turn`
+fn hue(value: &str) -> Option<f64> {
+    // `grad` comes before `rad` so it is not read as radians
+    let units = [("deg", 1.0), ("grad", 0.9), ("rad", 180.0 / std::f64::consts::PI), ("turn", 360.0)];
+    for (unit, degrees) in units {
+        if let Some(number) = value.strip_suffix(unit) {
+            return Some(number.parse::<f64>().ok()? * degrees);
+        }
+    }
+    value.parse::<f64>().ok()
+}
+
+fn parse_hsl(args: &str) -> Option<Rgba> {
+    let args = arguments(args);
+    if args.len() != 3 && args.len() != 4 {
+        return None;
+    }
+    let hue = hue(args[0])?.rem_euclid(360.0) / 360.0;
+    let saturation = number_or_percentage(args[1], 100.0)?.clamp(0.0, 100.0) / 100.0;
+    let lightness = number_or_percentage(args[2], 100.0)?.clamp(0.0, 100.0) / 100.0;
+
+    // the algorithm from the CSS colour specification
+    let t2 = if lightness <= 0.5 {
+        lightness * (saturation + 1.0)
+    } else {
+        lightness + saturation - lightness * saturation
+    };
+    let t1 = lightness * 2.0 - t2;
+    let hue_to_rgb = |h: f64| {
+        let h = h.rem_euclid(1.0);
+        if h * 6.0 < 1.0 {
+            t1 + (t2 - t1) * h * 6.0
+        } else if h * 2.0 < 1.0 {
+            t2
+        } else if h * 3.0 < 2.0 {
+            t1 + (t2 - t1) * (2.0 / 3.0 - h) * 6.0
+        } else {
+            t1
+        }
+    };
+    Some(Rgba::new(
+        channel(hue_to_rgb(hue + 1.0 / 3.0) * 255.0),
+        channel(hue_to_rgb(hue) * 255.0),
+        channel(hue_to_rgb(hue - 1.0 / 3.0) * 255.0),
+        alpha(&args)?,
+    ))
+}
+
+/// The 147 colour keywords of SVG 1.1 and CSS.
+const NAMED_COLORS: [(&str, [u8; 3]); 147] = [
+    ("aliceblue", [240, 248, 255]),
+    ("antiquewhite", [250, 235, 215]),
+    ("aqua", [0, 255, 255]),
+    ("aquamarine", [127, 255, 212]),
+    ("azure", [240, 255, 255]),
+    ("beige", [245, 245, 220]),
+    ("bisque", [255, 228, 196]),
+    ("black", [0, 0, 0]),
+    ("blanchedalmond", [255, 235, 205]),
+    ("blue", [0, 0, 255]),
+    ("blueviolet", [138, 43, 226]),
+    ("brown", [165, 42, 42]),
+    ("burlywood", [222, 184, 135]),
+    ("cadetblue", [95, 158, 160]),
+    ("chartreuse", [127, 255, 0]),
+    ("chocolate", [210, 105, 30]),
+    ("coral", [255, 127, 80]),
+    ("cornflowerblue", [100, 149, 237]),
+    ("cornsilk", [255, 248, 220]),
+    ("crimson", [220, 20, 60]),
+    ("cyan", [0, 255, 255]),
+    ("darkblue", [0, 0, 139]),
+    ("darkcyan", [0, 139, 139]),
+    ("darkgoldenrod", [184, 134, 11]),
+    ("darkgray", [169, 169, 169]),
+    ("darkgreen", [0, 100, 0]),
+    ("darkgrey", [169, 169, 169]),
+    ("darkkhaki", [189, 183, 107]),
+    ("darkmagenta", [139, 0, 139]),
+    ("darkolivegreen", [85, 107, 47]),
+    ("darkorange", [255, 140, 0]),
+    ("darkorchid", [153, 50, 204]),
+    ("darkred", [139, 0, 0]),
+    ("darksalmon", [233, 150, 122]),
+    ("darkseagreen", [143, 188, 143]),
+    ("darkslateblue", [72, 61, 139]),
+    ("darkslategray", [47, 79, 79]),
+    ("darkslategrey", [47, 79, 79]),
+    ("darkturquoise", [0, 206, 209]),
+    ("darkviolet", [148, 0, 211]),
+    ("deeppink", [255, 20, 147]),
+    ("deepskyblue", [0, 191, 255]),
+    ("dimgray", [105, 105, 105]),
+    ("dimgrey", [105, 105, 105]),
+    ("dodgerblue", [30, 144, 255]),
+    ("firebrick", [178, 34, 34]),
+    ("floralwhite", [255, 250, 240]),
+    ("forestgreen", [34, 139, 34]),
+    ("fuchsia", [255, 0, 255]),
+    ("gainsboro", [220, 220, 220]),
+    ("ghostwhite", [248, 248, 255]),
+    ("gold", [255, 215, 0]),
+    ("goldenrod", [218, 165, 32]),
+    ("gray", [128, 128, 128]),
+    ("grey", [128, 128, 128]),
+    ("green", [0, 128, 0]),
+    ("greenyellow", [173, 255, 47]),
+    ("honeydew", [240, 255, 240]),
+    ("hotpink", [255, 105, 180]),
+    ("indianred", [205, 92, 92]),
+    ("indigo", [75, 0, 130]),
+    ("ivory", [255, 255, 240]),
+    ("khaki", [240, 230, 140]),
+    ("lavender", [230, 230, 250]),
+    ("lavenderblush", [255, 240, 245]),
+    ("lawngreen", [124, 252, 0]),
+    ("lemonchiffon", [255, 250, 205]),
+    ("lightblue", [173, 216, 230]),
+    ("lightcoral", [240, 128, 128]),
+    ("lightcyan", [224, 255, 255]),
+    ("lightgoldenrodyellow", [250, 250, 210]),
+    ("lightgray", [211, 211, 211]),
+    ("lightgreen", [144, 238, 144]),
+    ("lightgrey", [211, 211, 211]),
+    ("lightpink", [255, 182, 193]),
+    ("lightsalmon", [255, 160, 122]),
+    ("lightseagreen", [32, 178, 170]),
+    ("lightskyblue", [135, 206, 250]),
+    ("lightslategray", [119, 136, 153]),
+    ("lightslategrey", [119, 136, 153]),
+    ("lightsteelblue", [176, 196, 222]),
+    ("lightyellow", [255, 255, 224]),
+    ("lime", [0, 255, 0]),
+    ("limegreen", [50, 205, 50]),
+    ("linen", [250, 240, 230]),
+    ("magenta", [255, 0, 255]),
+    ("maroon", [128, 0, 0]),
+    ("mediumaquamarine", [102, 205, 170]),
+    ("mediumblue", [0, 0, 205]),
+    ("mediumorchid", [186, 85, 211]),
+    ("mediumpurple", [147, 112, 219]),
+    ("mediumseagreen", [60, 179, 113]),
+    ("mediumslateblue", [123, 104, 238]),
+    ("mediumspringgreen", [0, 250, 154]),
+    ("mediumturquoise", [72, 209, 204]),
+    ("mediumvioletred", [199, 21, 133]),
+    ("midnightblue", [25, 25, 112]),
+    ("mintcream", [245, 255, 250]),
+    ("mistyrose", [255, 228, 225]),
+    ("moccasin", [255, 228, 181]),
+    ("navajowhite", [255, 222, 173]),
+    ("navy", [0, 0, 128]),
+    ("oldlace", [253, 245, 230]),
+    ("olive", [128, 128, 0]),
+    ("olivedrab", [107, 142, 35]),
+    ("orange", [255, 165, 0]),
+    ("orangered", [255, 69, 0]),
+    ("orchid", [218, 112, 214]),
+    ("palegoldenrod", [238, 232, 170]),
+    ("palegreen", [152, 251, 152]),
+    ("paleturquoise", [175, 238, 238]),
+    ("palevioletred", [219, 112, 147]),
+    ("papayawhip", [255, 239, 213]),
+    ("peachpuff", [255, 218, 185]),
+    ("peru", [205, 133, 63]),
+    ("pink", [255, 192, 203]),
+    ("plum", [221, 160, 221]),
+    ("powderblue", [176, 224, 230]),
+    ("purple", [128, 0, 128]),
+    ("red", [255, 0, 0]),
+    ("rosybrown", [188, 143, 143]),
+    ("royalblue", [65, 105, 225]),
+    ("saddlebrown", [139, 69, 19]),
+    ("salmon", [250, 128, 114]),
+    ("sandybrown", [244, 164, 96]),
+    ("seagreen", [46, 139, 87]),
+    ("seashell", [255, 245, 238]),
+    ("sienna", [160, 82, 45]),
+    ("silver", [192, 192, 192]),
+    ("skyblue", [135, 206, 235]),
+    ("slateblue", [106, 90, 205]),
+    ("slategray", [112, 128, 144]),
+    ("slategrey", [112, 128, 144]),
+    ("snow", [255, 250, 250]),
+    ("springgreen", [0, 255, 127]),
+    ("steelblue", [70, 130, 180]),
+    ("tan", [210, 180, 140]),
+    ("teal", [0, 128, 128]),
+    ("thistle", [216, 191, 216]),
+    ("tomato", [255, 99, 71]),
+    ("turquoise", [64, 224, 208]),
+    ("violet", [238, 130, 238]),
+    ("wheat", [245, 222, 179]),
+    ("white", [255, 255, 255]),
+    ("whitesmoke", [245, 245, 245]),
+    ("yellow", [255, 255, 0]),
+    ("yellowgreen", [154, 205, 50]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgba(value: &str) -> (u8, u8, u8, f64) {
+        let color = Rgba::parse(value).unwrap_or_else(|| panic!("{value:?} did not parse"));
+        (color.r, color.g, color.b, color.a)
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(rgba("#f80"), (255, 136, 0, 1.0));
+        assert_eq!(rgba("#ff880080"), (255, 136, 0, 128.0 / 255.0));
+        assert_eq!(rgba("#F808"), (255, 136, 0, 136.0 / 255.0));
+        assert_eq!(rgba("#123456"), (0x12, 0x34, 0x56, 1.0));
+        assert!(Rgba::parse("#12345").is_none());
+        assert!(Rgba::parse("#ggg").is_none());
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(rgba("rgb(255, 0, 0)"), (255, 0, 0, 1.0));
+        assert_eq!(rgba("rgb(100%, 50%, 0%)"), (255, 128, 0, 1.0));
+        assert_eq!(rgba("rgba(0, 0, 255, 0.5)"), (0, 0, 255, 0.5));
+        assert_eq!(rgba("rgb(0 0 255 / 25%)"), (0, 0, 255, 0.25));
+        assert_eq!(rgba("hsl(120, 100%, 25%)"), (0, 128, 0, 1.0));
+        assert_eq!(rgba("hsla(0.5turn, 100%, 50%, 0)"), (0, 255, 255, 0.0));
+        assert!(Rgba::parse("rgb(1, 2)").is_none());
+        assert!(Rgba::parse("lab(50% 40 59)").is_none());
+    }
+
+    #[test]
+    fn keywords() {
+        assert_eq!(rgba("purple"), (128, 0, 128, 1.0));
+        assert_eq!(rgba("LightGoldenrodYellow"), (250, 250, 210, 1.0));
+        assert!(Rgba::parse("transparent").unwrap().is_transparent());
+        assert!(Rgba::parse("currentColor").is_none());
+    }
+}
@@ -0,0 +1,400 @@
+use xml::attribute::OwnedAttribute;
+
+/// What a selector can see of an element.
+#[derive(Debug, Clone)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<OwnedAttribute>,
+}
+
+impl Element {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|i| i.name.local_name == name)
+            .map(|i| i.value.as_str())
+    }
+}
+
+/// How an attribute selector compares the attribute value.
+#[derive(Debug, Clone, PartialEq)]
+enum AttributeTest {
+    /// `[name]`
+    Exists,
+    /// `[name=value]`
+    Equals(String),
+    /// `[name~=value]`, one of the whitespace separated words
+    Includes(String),
+    /// `[name|=value]`, the value or the value followed by `-`
+    DashMatch(String),
+    /// `[name^=value]`
+    Prefix(String),
+    /// `[name$=value]`
+    Suffix(String),
+    /// `[name*=value]`
+    Substring(String),
+}
+
+impl AttributeTest {
+    fn matches(&self, value: &str) -> bool {
+        match self {
+            AttributeTest::Exists => true,
+            AttributeTest::Equals(expected) => value == expected,
+            AttributeTest::Includes(word) => value.split_whitespace().any(|w| w == word),
+            AttributeTest::DashMatch(expected) => {
+                value == expected || value.starts_with(&format!("{expected}-"))
+            }
+            AttributeTest::Prefix(prefix) => !prefix.is_empty() && value.starts_with(prefix),
+            AttributeTest::Suffix(suffix) => !suffix.is_empty() && value.ends_with(suffix),
+            AttributeTest::Substring(part) => !part.is_empty() && value.contains(part.as_str()),
+        }
+    }
+}
+
+/// Everything a selector says about a single element, such as `rect.cls-1#box[fill]`.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Compound {
+    /// `None` for `*` or when only other tests are given
+    element: Option<String>,
+    ids: Vec<String>,
+    classes: Vec<String>,
+    attributes: Vec<(String, AttributeTest)>,
+}
+
+impl Compound {
+    fn matches(&self, element: &Element) -> bool {
+        if self.element.as_ref().is_some_and(|name| *name != element.name) {
+            return false;
+        }
+        if !self.ids.iter().all(|id| element.attribute("id") == Some(id.as_str())) {
+            return false;
+        }
+        let classes = element.attribute("class").unwrap_or_default();
+        if !self
+            .classes
+            .iter()
+            .all(|class| classes.split_whitespace().any(|c| c == class))
+        {
+            return false;
+        }
+        self.attributes.iter().all(|(name, test)| {
+            element
+                .attribute(name)
+                .is_some_and(|value| test.matches(value))
+        })
+    }
+}
+
+/// How a compound relates to the one after it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Combinator {
+    /// whitespace, any ancestor
+    Descendant,
+    /// `>`, the parent
+    Child,
+}
+
+/// A complex selector. the last compound is the element itself and each
+/// earlier one is an ancestor, joined by the combinator before it
+#[derive(Debug, Clone, PartialEq)]
+struct Selector {
+    compounds: Vec<(Combinator, Compound)>,
+}
+
+impl Selector {
+    /// `None` for selectors that use syntax this parser does not know, which
+    /// drops the rule the way a browser would
+    fn parse(selector: &str) -> Option<Selector> {
+        let chars = selector.trim().chars().collect::<Vec<char>>();
+        let mut compounds = Vec::new();
+        let mut combinator = Combinator::Descendant;
+        let mut i = 0;
+        while i < chars.len() {
+            if chars[i].is_whitespace() {
+                i += 1;
+                continue;
+            }
+            if chars[i] == '>' {
+                if compounds.is_empty() || combinator == Combinator::Child {
+                    return None;
+                }
+                combinator = Combinator::Child;
+                i += 1;
+                continue;
+            }
+            let mut compound = Compound::default();
+            let start = i;
+            while i < chars.len() && !chars[i].is_whitespace() && chars[i] != '>' {
+                match chars[i] {
+                    '*' if i == start => i += 1,
+                    '#' => {
+                        let (name, next) = identifier(&chars, i + 1)?;
+                        compound.ids.push(name);
+                        i = next;
+                    }
+                    '.' => {
+                        let (name, next) = identifier(&chars, i + 1)?;
+                        compound.classes.push(name);
+                        i = next;
+                    }
+                    '[' => {
+                        let end = i + chars[i..].iter().position(|c| *c == ']')?;
+                        compound.attributes.push(attribute_selector(&chars[i + 1..end])?);
+                        i = end + 1;
+                    }
+                    _ if i == start => {
+                        let (name, next) = identifier(&chars, i)?;
+                        compound.element = Some(name);
+                        i = next;
+                    }
+                    // pseudo-classes, pseudo-elements and other combinators
+                    _ => return None,
+                }
+            }
+            compounds.push((combinator, compound));
+            combinator = Combinator::Descendant;
+        }
+        if compounds.is_empty() || combinator == Combinator::Child {
+            return None;
+        }
+        Some(Selector { compounds })
+    }
+
+    /// ids, then classes and attributes, then element names
+    fn specificity(&self) -> (usize, usize, usize) {
+        self.compounds
+            .iter()
+            .fold((0, 0, 0), |(ids, classes, elements), (_, compound)| {
+                (
+                    ids + compound.ids.len(),
+                    classes + compound.classes.len() + compound.attributes.len(),
+                    elements + compound.element.is_some() as usize,
+                )
+            })
+    }
+
+    /// does the last element of `path` match, given the elements above it
+    fn matches(&self, path: &[Element]) -> bool {
+        match path.split_last() {
+            Some((element, ancestors)) => match_from(&self.compounds, element, ancestors),
+            None => false,
+        }
+    }
+}
+
+/// match the last compound against `element`, then the rest against its ancestors.
+/// a descendant combinator tries every ancestor so a later failure can backtrack
+fn match_from(compounds: &[(Combinator, Compound)], element: &Element, ancestors: &[Element]) -> bool {
+    let Some(((combinator, compound), rest)) = compounds.split_last() else {
+        return true;
+    };
+    if !compound.matches(element) {
+        return false;
+    }
+    if rest.is_empty() {
+        return true;
+    }
+    match combinator {
+        Combinator::Child => match ancestors.split_last() {
+            Some((parent, above)) => match_from(rest, parent, above),
+            None => false,
+        },
+        Combinator::Descendant => (0..ancestors.len())
+            .rev()
+            .any(|i| match_from(rest, &ancestors[i], &ancestors[..i])),
+    }
+}
+
+/// an identifier starting at `i` and the index just past it
+fn identifier(chars: &[char], i: usize) -> Option<(String, usize)> {
+    let end = chars[i..]
+        .iter()
+        .position(|c| !(c.is_alphanumeric() || *c == '-' || *c == '_'))
+        .map_or(chars.len(), |n| i + n);
+    if end == i {
+        return None;
+    }
+    Some((chars[i..end].iter().collect(), end))
+}
+
+/// the inside of `[...]`
+fn attribute_selector(chars: &[char]) -> Option<(String, AttributeTest)> {
+    let inside = chars.iter().collect::<String>();
+    let Some(equals) = inside.find('=') else {
+        let name = inside.trim();
+        identifier(&name.chars().collect::<Vec<char>>(), 0).filter(|(n, _)| n == name)?;
+        return Some((name.to_string(), AttributeTest::Exists));
+    };
+    let (name, operator) = match inside[..equals].chars().last() {
+        Some(c @ ('~' | '|' | '^' | '$' | '*')) => (&inside[..equals - 1], Some(c)),
+        _ => (&inside[..equals], None),
+    };
+    let value = inside[equals + 1..].trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value)
+        .to_string();
+    let test = match operator {
+        None => AttributeTest::Equals(value),
+        Some('~') => AttributeTest::Includes(value),
+        Some('|') => AttributeTest::DashMatch(value),
+        Some('^') => AttributeTest::Prefix(value),
+        Some('$') => AttributeTest::Suffix(value),
+        _ => AttributeTest::Substring(value),
+    };
+    Some((name.trim().to_string(), test))
+}
+
+/// A style rule with a single selector, so `a, b { ... }` becomes two rules.
+#[derive(Debug, Clone)]
+struct Rule {
+    selector: Selector,
+    declarations: String,
+}
+
+/// The rules of every `<style>` element in a document, in document order.
+#[derive(Debug, Clone, Default)]
+pub struct Stylesheet {
+    rules: Vec<Rule>,
+}
+
+impl Stylesheet {
+    /// add the rules of a `<style>` element. at-rules such as `@media` and
+    /// rules with selectors that are not understood are skipped
+    pub fn parse(&mut self, css: &str) {
+        let css = strip_comments(css);
+        let mut rest = css.as_str();
+        while let Some(open) = rest.find('{') {
+            let prelude = rest[..open].trim();
+            // find the matching brace, at-rules can nest blocks
+            let mut depth = 0;
+            let mut close = rest.len();
+            for (i, c) in rest[open..].char_indices() {
+                match c {
+                    '{' => depth += 1,
+                    '}' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            close = open + i;
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            let block = &rest[open + 1..close];
+            rest = rest.get(close + 1..).unwrap_or_default();
+
+            // a stray statement at-rule like `@import url(a.css);` ends at its semicolon
+            let prelude = prelude.rsplit(';').next().unwrap_or_default().trim();
+            if prelude.starts_with('@') {
+                continue;
+            }
+            for selector in prelude.split(',') {
+                if let Some(selector) = Selector::parse(selector) {
+                    self.rules.push(Rule {
+                        selector,
+                        declarations: block.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// the declaration blocks of every rule that matches the last element of
+    /// `path`, from the lowest specificity to the highest so later ones win
+    pub fn matching(&self, path: &[Element]) -> Vec<&str> {
+        let mut rules = self
+            .rules
+            .iter()
+            .filter(|rule| rule.selector.matches(path))
+            .collect::<Vec<&Rule>>();
+        // the sort is stable, so rules of equal specificity keep document order
+        rules.sort_by_key(|rule| rule.selector.specificity());
+        rules.iter().map(|rule| rule.declarations.as_str()).collect()
+    }
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut stripped = String::new();
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        stripped.push_str(&rest[..start]);
+        rest = match rest[start + 2..].find("*/") {
+            Some(end) => &rest[start + 2 + end + 2..],
+            None => "",
+        };
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xml::name::OwnedName;
+
+    fn element(name: &str, attributes: &[(&str, &str)]) -> Element {
+        Element {
+            name: name.to_string(),
+            attributes: attributes
+                .iter()
+                .map(|(name, value)| OwnedAttribute::new(OwnedName::local(*name), *value))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn simple_selectors() {
+        let rect = element("rect", &[("id", "box"), ("class", "a cls-1"), ("fill", "red")]);
+        let matches = |selector: &str| Selector::parse(selector).unwrap().matches(std::slice::from_ref(&rect));
+        assert!(matches("rect"));
+        assert!(matches("*"));
+        assert!(matches(".cls-1"));
+        assert!(matches("rect.a.cls-1#box"));
+        assert!(matches("[fill]"));
+        assert!(matches("[fill=\"red\"]"));
+        assert!(matches("[class~=a]"));
+        assert!(matches("[id^=b][id$=x][id*=o]"));
+        assert!(!matches("circle"));
+        assert!(!matches(".cls"));
+        assert!(!matches("#other"));
+        assert!(!matches("[stroke]"));
+        assert!(Selector::parse("rect:hover").is_none());
+        assert!(Selector::parse("a + b").is_none());
+    }
+
+    #[test]
+    fn descendant_and_child_combinators() {
+        let path = [
+            element("svg", &[]),
+            element("g", &[("class", "outer")]),
+            element("g", &[("class", "inner")]),
+            element("path", &[]),
+        ];
+        let matches = |selector: &str| Selector::parse(selector).unwrap().matches(&path);
+        assert!(matches("svg path"));
+        assert!(matches(".outer path"));
+        assert!(matches(".outer > g > path"));
+        assert!(matches("svg .inner > path"));
+        assert!(!matches(".outer > path"));
+        assert!(!matches(".inner .outer path"));
+    }
+
+    #[test]
+    fn cascade_orders_by_specificity_then_source() {
+        let mut stylesheet = Stylesheet::default();
+        stylesheet.parse(
+            "/* generated */ #box { fill: red } .cls-1, circle { fill: blue }
+             @media print { rect { fill: black } }
+             rect { fill: green } .cls-1 { stroke: none }",
+        );
+        let path = [element("rect", &[("id", "box"), ("class", "cls-1")])];
+        assert_eq!(
+            stylesheet.matching(&path),
+            vec![" fill: green ", " fill: blue ", " stroke: none ", " fill: red "]
+        );
+    }
+}
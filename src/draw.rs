@@ -1,6 +1,11 @@
-use crate::canvas::canvas_draw;
+use crate::canvas::{canvas_bounds, canvas_draw, dot_size, text_draw};
 use crate::canvas::SvgDataset;
-use crate::svg::{render_svg, SvgPoints};
+use crate::clip::clip;
+use crate::error::SvgError;
+use crate::fill::scanline_fill;
+use crate::stroke::{dash, stroke_outline};
+use crate::style::FillRule;
+use crate::svg::{is_document, render_svg, SvgPoints, ViewBox};
 use ratatui::widgets::GraphType::Line as OtherLine;
 use ratatui::{
     backend::CrosstermBackend,
@@ -8,11 +13,86 @@ use ratatui::{
     style::{Color, Style},
     symbols, Frame,
 };
-use regex::Regex;
+use itertools::Itertools;
 use std::{collections::HashMap, io::Stdout};
 
+/// How `<text>` is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TextMode {
+    /// as terminal characters over the canvas
+    #[default]
+    Cells,
+    /// as lines on the canvas, traced from a built in single stroke font
+    Strokes,
+}
+
+/// Settings that change how an svg is drawn, rather than what it contains.
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// height of a terminal cell divided by its width
+    pub cell_aspect: f64,
+    /// how far a flattened curve may stray from the true curve, in braille dots
+    pub tolerance: f64,
+    pub text_mode: TextMode,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            cell_aspect: 2.0,
+            tolerance: 0.25,
+            text_mode: TextMode::Cells,
+        }
+    }
+}
+
+/// Points ready to be drawn as braille dots, and their colour.
+type Drawing = (Vec<(f64, f64, bool)>, Color);
+
+/// the points to draw for a shape and their colour, given the canvas units
+/// between braille dots and the flattening tolerance in dots. fills and wide
+/// strokes are only turned into spans here, once the size of a dot is known
+fn rasterise(
+    (points, style, filled, clips, closed): SvgPoints,
+    view_box: ViewBox,
+    (dot_width, dot_height): (f64, f64),
+    tolerance: f64,
+) -> Option<Drawing> {
+    let (points, color) = if filled {
+        let color = style.fill_color()?;
+        (scanline_fill(&points, style.fill_rule, dot_height), color)
+    } else {
+        // no stroke colour or width means there is no outline to draw
+        let color = style.stroke_color().filter(|_| style.stroke_width > 0.0)?;
+        // a canvas unit is a different length across and down unless the view
+        // box is square, so the stroke is laid out in the user units of the root
+        let unit = (view_box.x_scale(), view_box.y_scale());
+        let scale = |points: &[(f64, f64, bool)], by: (f64, f64)| {
+            points.iter().map(|(x, y, pen)| (x * by.0, y * by.1, *pen)).collect::<Vec<_>>()
+        };
+        let (points, closed) = dash(&scale(&points, unit), &closed, &style.stroke_dasharray, style.stroke_dashoffset);
+        let to_canvas = (1.0 / unit.0, 1.0 / unit.1);
+        if style.stroke_width <= (dot_width * unit.0).max(dot_height * unit.1) {
+            // a hairline is drawn one dot wide so it never vanishes
+            (scale(&points, to_canvas), color)
+        } else {
+            let tolerance = tolerance * (dot_width * unit.0).min(dot_height * unit.1);
+            let outline = scale(&stroke_outline(&points, &closed, &style, tolerance), to_canvas);
+            (scanline_fill(&outline, FillRule::NonZero, dot_height), color)
+        }
+    };
+    // nested viewports hide whatever is drawn outside them
+    let points = clips.iter().fold(points, |points, polygon| clip(&points, polygon));
+    Some((points, color))
+}
+
 /// parse and render the svg to the terminal
-pub fn draw_svg(strings: Vec<String>, frame: &mut Frame<CrosstermBackend<Stdout>>, layout: Rect) {
+pub fn draw_svg(
+    strings: Vec<String>,
+    frame: &mut Frame<CrosstermBackend<Stdout>>,
+    layout: Rect,
+    options: &RenderOptions,
+) -> Result<(), SvgError> {
     //let width = layout.width as f64;
     //let height = layout.height as f64;
     //let ratio = width / height;
@@ -28,90 +108,83 @@ pub fn draw_svg(strings: Vec<String>, frame: &mut Frame<CrosstermBackend<Stdout>
     let footer = r#"</svg>"#;
 
     let mut draw_svg = String::new();
-    draw_svg.push_str(header_1);
-    draw_svg.push_str(header_2);
-    for i in strings {
-        draw_svg.push_str(&i);
+    // loaded files are complete documents, snippets get the default 100 by 100 root
+    let document = strings.concat();
+    if is_document(&document) {
+        draw_svg.push_str(&document);
+    } else {
+        draw_svg.push_str(header_1);
+        draw_svg.push_str(header_2);
+        draw_svg.push_str(&document);
+        draw_svg.push_str(footer);
     }
-    draw_svg.push_str(footer);
     //save svg to file
 
     let mut hash_map: HashMap<usize, SvgPoints> = HashMap::new();
-    render_svg(draw_svg, &mut hash_map);
+    let mut texts = Vec::new();
+    let (view_box, aspect_ratio) = render_svg(draw_svg, &mut hash_map, &mut texts, layout, options)?;
+    let bounds = canvas_bounds(view_box, aspect_ratio, layout, options.cell_aspect);
+    let dot = dot_size(bounds, layout);
+
+    // the keys are in paint order
+    let shapes = hash_map
+        .into_iter()
+        .sorted_by_key(|(key, _)| *key)
+        .filter_map(|(_, shape)| rasterise(shape, view_box, dot, options.tolerance))
+        .collect::<Vec<Drawing>>();
 
     let mut datasets = Vec::new();
-    let re = Regex::new(r"stroke:\s*rgb\((\d+),\s*(\d+),\s*(\d+)\);").unwrap();
-    let bg_re = Regex::new(r"fill:\s*rgb\((\d+),\s*(\d+),\s*(\d+)\);").unwrap();
-    for i in hash_map.values() {
-        /*         let f = File::create("test.txt").unwrap();
-        let mut f = BufWriter::new(f);
-        for j in i.0.iter() {
-            writeln!(f, "{j:?}").unwrap();
-        } */
-        if i.2 {
-            let bg_color = Color::Rgb(
-                bg_re
-                    .captures(&i.1)
-                    .unwrap()
-                    .get(1)
-                    .unwrap()
-                    .as_str()
-                    .parse::<u8>()
-                    .unwrap(),
-                bg_re
-                    .captures(&i.1)
-                    .unwrap()
-                    .get(1)
-                    .unwrap()
-                    .as_str()
-                    .parse::<u8>()
-                    .unwrap(),
-                bg_re
-                    .captures(&i.1)
-                    .unwrap()
-                    .get(1)
-                    .unwrap()
-                    .as_str()
-                    .parse::<u8>()
-                    .unwrap(),
-            );
-            let dataset = SvgDataset::default()
-                .data(&i.0)
-                .marker(symbols::Marker::Blocks(symbols::Blocks::FULL))
-                .graph_type(OtherLine)
-                .style(Style::default().fg(bg_color));
-            datasets.push(dataset);
-        } else {
-            let color = Color::Rgb(
-                re.captures(&i.1)
-                    .unwrap()
-                    .get(1)
-                    .unwrap()
-                    .as_str()
-                    .parse::<u8>()
-                    .unwrap(),
-                re.captures(&i.1)
-                    .unwrap()
-                    .get(2)
-                    .unwrap()
-                    .as_str()
-                    .parse::<u8>()
-                    .unwrap(),
-                re.captures(&i.1)
-                    .unwrap()
-                    .get(3)
-                    .unwrap()
-                    .as_str()
-                    .parse::<u8>()
-                    .unwrap(),
-            );
-            let dataset = SvgDataset::default()
-                .data(&i.0)
-                .marker(symbols::Marker::Braille)
-                .graph_type(OtherLine)
-                .style(Style::default().fg(color));
-            datasets.push(dataset);
-        }
+    for (points, color) in &shapes {
+        let dataset = SvgDataset::default()
+            .data(points)
+            .marker(symbols::Marker::Braille)
+            .graph_type(OtherLine)
+            .style(Style::default().fg(*color));
+        datasets.push(dataset);
+    }
+    canvas_draw(frame, layout, datasets, bounds);
+    // text goes over the braille dots
+    text_draw(frame, layout, &texts, bounds);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the spans drawn for the shapes of `svg` in a 200 by 100 cell layout
+    fn spans(svg: &str) -> Vec<(f64, f64, bool)> {
+        let (mut hash_map, mut texts) = (HashMap::new(), Vec::new());
+        let layout = Rect::new(0, 0, 200, 100);
+        let options = RenderOptions::default();
+        let (view_box, aspect_ratio) = render_svg(svg.to_string(), &mut hash_map, &mut texts, layout, &options).unwrap();
+        let dot = dot_size(canvas_bounds(view_box, aspect_ratio, layout, options.cell_aspect), layout);
+        hash_map
+            .into_iter()
+            .sorted_by_key(|(key, _)| *key)
+            .filter_map(|(_, shape)| rasterise(shape, view_box, dot, options.tolerance))
+            .flat_map(|(points, _)| points)
+            .collect()
+    }
+
+    #[test]
+    fn strokes_are_as_wide_across_as_down_a_wide_view_box() {
+        // a canvas unit is two user units across and one down
+        let spans = spans(
+            r#"<svg viewBox="0 0 200 100"><path d="M 20 50 H 180 M 100 10 V 90" fill="none" stroke="red" stroke-width="10"/></svg>"#,
+        );
+        let spans = spans.chunks_exact(2).map(|span| (span[0], span[1])).collect::<Vec<_>>();
+        // the horizontal line, crossed at x = 50 where the vertical line is not
+        let rows = spans
+            .iter()
+            .filter(|(start, end)| start.0 <= 25.0 && 25.0 <= end.0)
+            .map(|(start, _)| start.1)
+            .collect::<Vec<f64>>();
+        let height = rows.iter().cloned().fold(f64::MIN, f64::max) - rows.iter().cloned().fold(f64::MAX, f64::min);
+        assert!((9.0..=10.5).contains(&height), "{height}");
+        // the vertical line, at y = 30 where the horizontal line is not
+        let (start, end) = spans.iter().find(|(start, _)| (start.1 - 70.0).abs() < 0.3).unwrap();
+        let width = (end.0 - start.0) * 2.0;
+        assert!((9.0..=10.5).contains(&width), "{width}");
     }
-    canvas_draw(frame, layout, datasets)
 }
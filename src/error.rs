@@ -0,0 +1,71 @@
+use std::{fmt, io};
+
+use crate::path::PathError;
+
+/// Everything that can go wrong while loading, parsing or drawing an SVG.
+#[derive(Debug)]
+pub enum SvgError {
+    /// The document is not well formed XML.
+    Xml(xml::reader::Error),
+    /// An attribute value could not be parsed.
+    Attribute { name: String, value: String },
+    /// The `d` attribute of a path could not be parsed.
+    PathData(PathError),
+    /// An element that cannot be drawn, such as embedded content.
+    Unsupported(String),
+    /// The `<use>` elements make more copies than this between them.
+    Instances(usize),
+    /// A watched SVG file could not be read.
+    Io(io::Error),
+}
+
+impl SvgError {
+    pub fn attribute(name: &str, value: &str) -> SvgError {
+        SvgError::Attribute {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for SvgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SvgError::Xml(error) => write!(f, "invalid XML: {error}"),
+            SvgError::Attribute { name, value } => write!(f, "invalid `{name}` attribute: {value:?}"),
+            SvgError::PathData(error) => write!(f, "{error}"),
+            SvgError::Unsupported(name) => write!(f, "unsupported element `<{name}>`"),
+            SvgError::Instances(limit) => write!(f, "more than {limit} `<use>` copies"),
+            SvgError::Io(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for SvgError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SvgError::Xml(error) => Some(error),
+            SvgError::PathData(error) => Some(error),
+            SvgError::Io(error) => Some(error),
+            SvgError::Attribute { .. } | SvgError::Unsupported(_) | SvgError::Instances(_) => None,
+        }
+    }
+}
+
+impl From<xml::reader::Error> for SvgError {
+    fn from(error: xml::reader::Error) -> Self {
+        SvgError::Xml(error)
+    }
+}
+
+impl From<PathError> for SvgError {
+    fn from(error: PathError) -> Self {
+        SvgError::PathData(error)
+    }
+}
+
+impl From<io::Error> for SvgError {
+    fn from(error: io::Error) -> Self {
+        SvgError::Io(error)
+    }
+}
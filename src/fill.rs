@@ -0,0 +1,142 @@
+use crate::style::FillRule;
+
+/// split an outline into its subpaths, a point with the pen up starts a new one
+pub fn subpaths(outline: &[(f64, f64, bool)]) -> Vec<Vec<(f64, f64)>> {
+    let mut subpaths: Vec<Vec<(f64, f64)>> = Vec::new();
+    for (i, point) in outline.iter().enumerate() {
+        if i == 0 || !point.2 {
+            subpaths.push(Vec::new());
+        }
+        if let Some(subpath) = subpaths.last_mut() {
+            subpath.push((point.0, point.1));
+        }
+    }
+    subpaths
+}
+
+/// the edges of every subpath of an outline, each subpath closed back to its start
+fn edges(outline: &[(f64, f64, bool)]) -> Vec<((f64, f64), (f64, f64))> {
+    let mut edges = Vec::new();
+    for subpath in subpaths(outline).iter().filter(|subpath| subpath.len() > 1) {
+        for (i, start) in subpath.iter().enumerate() {
+            let end = subpath[(i + 1) % subpath.len()];
+            if start.1 != end.1 {
+                edges.push((*start, end));
+            }
+        }
+    }
+    edges
+}
+
+/// fill an outline with horizontal spans, one scanline every `step` canvas units.
+/// each span is a pen up point followed by a pen down point, so drawing the
+/// result as lines paints exactly the inside of the outline
+pub fn scanline_fill(outline: &[(f64, f64, bool)], rule: FillRule, step: f64) -> Vec<(f64, f64, bool)> {
+    let mut spans = Vec::new();
+    let edges = edges(outline);
+    if edges.is_empty() || step <= 0.0 || !step.is_finite() {
+        return spans;
+    }
+    let y_min = edges.iter().map(|(a, b)| a.1.min(b.1)).fold(f64::INFINITY, f64::min);
+    let y_max = edges.iter().map(|(a, b)| a.1.max(b.1)).fold(f64::NEG_INFINITY, f64::max);
+
+    // scanlines sit in the middle of each step, on a grid shared by every shape
+    // so neighbouring fills meet without gaps or overlaps
+    let mut row = (y_min / step - 0.5).ceil();
+    let mut crossings: Vec<(f64, i32)> = Vec::new();
+    while (row + 0.5) * step < y_max {
+        let y = (row + 0.5) * step;
+        crossings.clear();
+        for (start, end) in &edges {
+            // half open so a vertex shared by two edges is only counted once
+            if (start.1 <= y) != (end.1 <= y) {
+                let x = start.0 + (y - start.1) / (end.1 - start.1) * (end.0 - start.0);
+                let direction = if end.1 > start.1 { 1 } else { -1 };
+                crossings.push((x, direction));
+            }
+        }
+        crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut winding = 0;
+        let mut span_start = 0.0;
+        for (x, direction) in &crossings {
+            let was_inside = rule.is_inside(winding);
+            winding += direction;
+            let inside = rule.is_inside(winding);
+            if inside && !was_inside {
+                span_start = *x;
+            } else if was_inside && !inside && *x > span_start {
+                spans.push((span_start, y, false));
+                spans.push((*x, y, true));
+            }
+        }
+        row += 1.0;
+    }
+    spans
+}
+
+/// is (x, y) on one of the spans of a fill
+#[cfg(test)]
+pub(crate) fn covers(spans: &[(f64, f64, bool)], x: f64, y: f64) -> bool {
+    spans
+        .chunks_exact(2)
+        .any(|span| (span[0].1 - y).abs() < 0.5 && span[0].0 <= x && x <= span[1].0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f64, y: f64, size: f64, clockwise: bool) -> Vec<(f64, f64, bool)> {
+        let mut corners = [(x, y), (x + size, y), (x + size, y + size), (x, y + size)];
+        if !clockwise {
+            corners.reverse();
+        }
+        let mut points = corners.iter().map(|(x, y)| (*x, *y, true)).collect::<Vec<_>>();
+        points[0].2 = false;
+        points
+    }
+
+    #[test]
+    fn concave_shapes_only_fill_their_inside() {
+        // an L shape, the old bounding box fill also covered the top right
+        let outline = [
+            (0.0, 0.0, true),
+            (20.0, 0.0, true),
+            (20.0, 10.0, true),
+            (10.0, 10.0, true),
+            (10.0, 20.0, true),
+            (0.0, 20.0, true),
+        ];
+        let spans = scanline_fill(&outline, FillRule::NonZero, 1.0);
+        assert!(covers(&spans, 5.0, 15.5));
+        assert!(covers(&spans, 15.0, 5.5));
+        assert!(!covers(&spans, 15.0, 15.5));
+    }
+
+    #[test]
+    fn holes_follow_the_fill_rule() {
+        let mut same_direction = square(0.0, 0.0, 30.0, true);
+        same_direction.extend(square(10.0, 10.0, 10.0, true));
+        let mut opposite_direction = square(0.0, 0.0, 30.0, true);
+        opposite_direction.extend(square(10.0, 10.0, 10.0, false));
+
+        let nonzero = scanline_fill(&same_direction, FillRule::NonZero, 1.0);
+        assert!(covers(&nonzero, 15.0, 15.5));
+        let evenodd = scanline_fill(&same_direction, FillRule::EvenOdd, 1.0);
+        assert!(!covers(&evenodd, 15.0, 15.5));
+        assert!(covers(&evenodd, 5.0, 15.5));
+        let nonzero = scanline_fill(&opposite_direction, FillRule::NonZero, 1.0);
+        assert!(!covers(&nonzero, 15.0, 15.5));
+    }
+
+    #[test]
+    fn separate_subpaths_fill_separately() {
+        let mut outline = square(0.0, 0.0, 10.0, true);
+        outline.extend(square(20.0, 0.0, 10.0, false));
+        let spans = scanline_fill(&outline, FillRule::NonZero, 1.0);
+        assert!(covers(&spans, 5.0, 5.5));
+        assert!(covers(&spans, 25.0, 5.5));
+        assert!(!covers(&spans, 15.0, 5.5));
+    }
+}
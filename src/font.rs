@@ -0,0 +1,136 @@
+/// the size of the font on the glyph grid, so a glyph is scaled by `font-size / EM`
+pub const EM: f64 = 10.0;
+
+/// how far the pen moves after every glyph, which is the same for all of them
+pub const ADVANCE: f64 = 6.0;
+
+/// the path data of a glyph in a single stroke font, in the spirit of the Hershey
+/// fonts, or `None` for a character the font does not have. the pen starts at
+/// x = 0 on the baseline at y = 0, capitals reach up to y = -7, lower case letters
+/// to y = -5 and descenders down to y = 2, with y pointing down like user space.
+/// a space is an empty path, it only moves the pen
+pub fn glyph(c: char) -> Option<&'static str> {
+    let d = match c {
+        ' ' => "",
+        '!' => "M 3 -7 L 3 -2 M 3 -0.5 L 3 0",
+        '"' => "M 2 -7 L 2 -5 M 4 -7 L 4 -5",
+        '#' => "M 2 -6 L 2 -1 M 4 -6 L 4 -1 M 1 -4.5 L 5 -4.5 M 1 -2.5 L 5 -2.5",
+        '$' => "M 5 -6 Q 4.5 -7 3 -7 Q 1 -7 1 -5.25 Q 1 -3.5 3 -3.5 Q 5 -3.5 5 -1.75 Q 5 0 3 0 Q 1.5 0 1 -1 M 3 -8 L 3 1",
+        '%' => "M 1 0 L 5 -7 M 2 -7 A 1 1 0 0 0 2 -5 A 1 1 0 0 0 2 -7 M 4 -2 A 1 1 0 0 0 4 0 A 1 1 0 0 0 4 -2",
+        '&' => "M 5 0 L 2 -5 Q 1.5 -7 3 -7 Q 4.5 -7 4 -5 L 1.5 -2.5 Q 1 0 3 0 Q 4 0 5 -2",
+        '\'' => "M 3 -7 L 3 -5",
+        '(' => "M 4 -7.5 Q 1.5 -3.5 4 0.5",
+        ')' => "M 2 -7.5 Q 4.5 -3.5 2 0.5",
+        '*' => "M 3 -6 L 3 -2 M 1.5 -5 L 4.5 -3 M 1.5 -3 L 4.5 -5",
+        '+' => "M 3 -5.5 L 3 -1.5 M 1 -3.5 L 5 -3.5",
+        ',' => "M 3 -0.5 L 2.5 1.5",
+        '-' => "M 1.5 -3.5 L 4.5 -3.5",
+        '.' => "M 3 -0.5 L 3 0",
+        '/' => "M 1 0.5 L 5 -7.5",
+        '0' => "M 3 -7 A 2 3.5 0 0 0 3 0 A 2 3.5 0 0 0 3 -7 M 4.5 -6 L 1.5 -1",
+        '1' => "M 2 -5.5 L 3.5 -7 L 3.5 0 M 2 0 L 5 0",
+        '2' => "M 1 -5.5 Q 1.5 -7 3 -7 Q 5 -7 5 -5 Q 5 -3.5 1 0 L 5 0",
+        '3' => "M 1 -6.5 Q 2 -7 3 -7 Q 5 -7 5 -5.25 Q 5 -3.5 3 -3.5 Q 5 -3.5 5 -1.75 Q 5 0 3 0 Q 2 0 1 -0.5",
+        '4' => "M 4 0 L 4 -7 L 1 -2 L 5 -2",
+        '5' => "M 5 -7 L 1.5 -7 L 1 -4 Q 2 -4.5 3 -4.5 Q 5 -4.5 5 -2.25 Q 5 0 3 0 Q 2 0 1 -0.5",
+        '6' => "M 4.5 -7 Q 1 -6 1 -2 Q 1 0 3 0 Q 5 0 5 -2 Q 5 -4 3 -4 Q 1.5 -4 1 -2.5",
+        '7' => "M 1 -7 L 5 -7 L 2.5 0",
+        '8' => "M 3 -3.5 Q 1.25 -3.5 1.25 -5.25 Q 1.25 -7 3 -7 Q 4.75 -7 4.75 -5.25 Q 4.75 -3.5 3 -3.5 Q 1 -3.5 1 -1.75 Q 1 0 3 0 Q 5 0 5 -1.75 Q 5 -3.5 3 -3.5",
+        '9' => "M 1.5 0 Q 5 -1 5 -5 Q 5 -7 3 -7 Q 1 -7 1 -5 Q 1 -3 3 -3 Q 4.5 -3 5 -4.5",
+        ':' => "M 3 -5 L 3 -4.5 M 3 -0.5 L 3 0",
+        ';' => "M 3 -5 L 3 -4.5 M 3 -0.5 L 2.5 1.5",
+        '<' => "M 5 -6 L 1 -3.5 L 5 -1",
+        '=' => "M 1 -4.5 L 5 -4.5 M 1 -2.5 L 5 -2.5",
+        '>' => "M 1 -6 L 5 -3.5 L 1 -1",
+        '?' => "M 1 -5.5 Q 1.5 -7 3 -7 Q 5 -7 5 -5.25 Q 5 -4 3 -3.5 L 3 -2 M 3 -0.5 L 3 0",
+        '@' => "M 4 -4.5 A 1 1 0 1 0 4 -2.5 L 4 -4.5 M 4 -2.5 Q 5 -2 5 -4 Q 5 -7 3 -7 Q 1 -7 1 -3.5 Q 1 0 3 0 L 4.5 0",
+        'A' => "M 1 0 L 3 -7 L 5 0 M 1.6 -2.5 L 4.4 -2.5",
+        'B' => "M 1 0 L 1 -7 L 3.5 -7 Q 5 -7 5 -5.25 Q 5 -3.5 3.5 -3.5 L 1 -3.5 M 3.5 -3.5 Q 5 -3.5 5 -1.75 Q 5 0 3.5 0 L 1 0",
+        'C' => "M 5 -6 Q 4.5 -7 3 -7 Q 1 -7 1 -3.5 Q 1 0 3 0 Q 4.5 0 5 -1",
+        'D' => "M 1 0 L 1 -7 L 2.5 -7 Q 5 -7 5 -3.5 Q 5 0 2.5 0 Z",
+        'E' => "M 5 -7 L 1 -7 L 1 0 L 5 0 M 1 -3.5 L 4 -3.5",
+        'F' => "M 5 -7 L 1 -7 L 1 0 M 1 -3.5 L 4 -3.5",
+        'G' => "M 5 -6 Q 4.5 -7 3 -7 Q 1 -7 1 -3.5 Q 1 0 3 0 Q 5 0 5 -2 L 5 -3 L 3.5 -3",
+        'H' => "M 1 -7 L 1 0 M 5 -7 L 5 0 M 1 -3.5 L 5 -3.5",
+        'I' => "M 2 -7 L 4 -7 M 3 -7 L 3 0 M 2 0 L 4 0",
+        'J' => "M 5 -7 L 5 -2 Q 5 0 3 0 Q 1 0 1 -2",
+        'K' => "M 1 -7 L 1 0 M 5 -7 L 1 -2.5 M 2.5 -4 L 5 0",
+        'L' => "M 1 -7 L 1 0 L 5 0",
+        'M' => "M 1 0 L 1 -7 L 3 -3 L 5 -7 L 5 0",
+        'N' => "M 1 0 L 1 -7 L 5 0 L 5 -7",
+        'O' => "M 3 -7 A 2 3.5 0 0 0 3 0 A 2 3.5 0 0 0 3 -7",
+        'P' => "M 1 0 L 1 -7 L 3.5 -7 Q 5 -7 5 -5.25 Q 5 -3.5 3.5 -3.5 L 1 -3.5",
+        'Q' => "M 3 -7 A 2 3.5 0 0 0 3 0 A 2 3.5 0 0 0 3 -7 M 3.5 -1.5 L 5 0.5",
+        'R' => "M 1 0 L 1 -7 L 3.5 -7 Q 5 -7 5 -5.25 Q 5 -3.5 3.5 -3.5 L 1 -3.5 M 3 -3.5 L 5 0",
+        'S' => "M 5 -6 Q 4.5 -7 3 -7 Q 1 -7 1 -5.25 Q 1 -3.5 3 -3.5 Q 5 -3.5 5 -1.75 Q 5 0 3 0 Q 1.5 0 1 -1",
+        'T' => "M 1 -7 L 5 -7 M 3 -7 L 3 0",
+        'U' => "M 1 -7 L 1 -2 Q 1 0 3 0 Q 5 0 5 -2 L 5 -7",
+        'V' => "M 1 -7 L 3 0 L 5 -7",
+        'W' => "M 1 -7 L 2 0 L 3 -4 L 4 0 L 5 -7",
+        'X' => "M 1 -7 L 5 0 M 5 -7 L 1 0",
+        'Y' => "M 1 -7 L 3 -3.5 L 5 -7 M 3 -3.5 L 3 0",
+        'Z' => "M 1 -7 L 5 -7 L 1 0 L 5 0",
+        '[' => "M 4 -7.5 L 2.5 -7.5 L 2.5 0.5 L 4 0.5",
+        '\\' => "M 1 -7.5 L 5 0.5",
+        ']' => "M 2 -7.5 L 3.5 -7.5 L 3.5 0.5 L 2 0.5",
+        '^' => "M 1.5 -5 L 3 -7 L 4.5 -5",
+        '_' => "M 1 1 L 5 1",
+        '`' => "M 2.5 -7 L 3.5 -6",
+        'a' => "M 5 -5 L 5 0 M 5 -2.5 Q 5 -5 3 -5 Q 1 -5 1 -2.5 Q 1 0 3 0 Q 5 0 5 -2.5",
+        'b' => "M 1 -7 L 1 0 M 1 -2.5 Q 1 -5 3 -5 Q 5 -5 5 -2.5 Q 5 0 3 0 Q 1 0 1 -2.5",
+        'c' => "M 5 -4 Q 4.5 -5 3 -5 Q 1 -5 1 -2.5 Q 1 0 3 0 Q 4.5 0 5 -1",
+        'd' => "M 5 -7 L 5 0 M 5 -2.5 Q 5 -5 3 -5 Q 1 -5 1 -2.5 Q 1 0 3 0 Q 5 0 5 -2.5",
+        'e' => "M 1 -2.5 L 5 -2.5 Q 5 -5 3 -5 Q 1 -5 1 -2.5 Q 1 0 3 0 Q 4.5 0 5 -1",
+        'f' => "M 5 -7 Q 3 -7.5 3 -5.5 L 3 0 M 1.5 -5 L 4.5 -5",
+        'g' => "M 5 -5 L 5 0.5 Q 5 2 3 2 Q 1.5 2 1 1 M 5 -2.5 Q 5 -5 3 -5 Q 1 -5 1 -2.5 Q 1 0 3 0 Q 5 0 5 -2.5",
+        'h' => "M 1 -7 L 1 0 M 1 -3 Q 1.5 -5 3 -5 Q 5 -5 5 -3 L 5 0",
+        'i' => "M 3 -5 L 3 0 M 3 -7 L 3 -6.5",
+        'j' => "M 4 -5 L 4 0.5 Q 4 2 2.5 2 Q 1.5 2 1 1.5 M 4 -7 L 4 -6.5",
+        'k' => "M 1 -7 L 1 0 M 4.5 -5 L 1 -2 M 2.2 -3 L 5 0",
+        'l' => "M 2.5 -7 L 2.5 -1 Q 2.5 0 3.5 0 L 4 0",
+        'm' => "M 1 -5 L 1 0 M 1 -3.5 Q 1.5 -5 2.25 -5 Q 3 -5 3 -3.5 L 3 0 M 3 -3.5 Q 3.5 -5 4.25 -5 Q 5 -5 5 -3.5 L 5 0",
+        'n' => "M 1 -5 L 1 0 M 1 -3 Q 1.5 -5 3 -5 Q 5 -5 5 -3 L 5 0",
+        'o' => "M 3 -5 A 2 2.5 0 0 0 3 0 A 2 2.5 0 0 0 3 -5",
+        'p' => "M 1 -5 L 1 2 M 1 -2.5 Q 1 -5 3 -5 Q 5 -5 5 -2.5 Q 5 0 3 0 Q 1 0 1 -2.5",
+        'q' => "M 5 -5 L 5 2 M 5 -2.5 Q 5 -5 3 -5 Q 1 -5 1 -2.5 Q 1 0 3 0 Q 5 0 5 -2.5",
+        'r' => "M 1.5 -5 L 1.5 0 M 1.5 -3 Q 2 -5 4 -5 L 5 -4.5",
+        's' => "M 5 -4.5 Q 4 -5 3 -5 Q 1 -5 1 -3.75 Q 1 -2.5 3 -2.5 Q 5 -2.5 5 -1.25 Q 5 0 3 0 Q 2 0 1 -0.5",
+        't' => "M 3 -6.5 L 3 -1 Q 3 0 4 0 L 4.5 0 M 1.5 -5 L 4.5 -5",
+        'u' => "M 1 -5 L 1 -2 Q 1 0 3 0 Q 4.5 0 5 -2 M 5 -5 L 5 0",
+        'v' => "M 1 -5 L 3 0 L 5 -5",
+        'w' => "M 1 -5 L 2 0 L 3 -3 L 4 0 L 5 -5",
+        'x' => "M 1 -5 L 5 0 M 5 -5 L 1 0",
+        'y' => "M 1 -5 L 3 0 M 5 -5 L 2.5 1.5 Q 2 2 1 2",
+        'z' => "M 1 -5 L 5 -5 L 1 0 L 5 0",
+        '{' => "M 4 -7.5 Q 3 -7.5 3 -6 L 3 -4.5 Q 3 -3.5 2 -3.5 Q 3 -3.5 3 -2.5 L 3 -1 Q 3 0.5 4 0.5",
+        '|' => "M 3 -7.5 L 3 0.5",
+        '}' => "M 2 -7.5 Q 3 -7.5 3 -6 L 3 -4.5 Q 3 -3.5 4 -3.5 Q 3 -3.5 3 -2.5 L 3 -1 Q 3 0.5 2 0.5",
+        '~' => "M 1 -3 Q 2 -4.5 3 -3.5 Q 4 -2.5 5 -4",
+        _ => return None,
+    };
+    Some(d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::parse_path;
+
+    #[test]
+    fn every_printable_character_has_a_glyph_that_parses() {
+        for c in ' '..='~' {
+            let d = glyph(c).unwrap_or_else(|| panic!("no glyph for {c:?}"));
+            let commands = parse_path(d).unwrap_or_else(|error| panic!("{c:?}: {error}"));
+            // every glyph stays between the side bearings of its advance
+            for (command, args) in commands {
+                if command != 'A' {
+                    for pair in args.chunks_exact(2) {
+                        assert!((0.0..ADVANCE).contains(&pair[0]), "{c:?} leaves its advance");
+                        assert!((-8.0..=2.0).contains(&pair[1]), "{c:?} is too tall");
+                    }
+                }
+            }
+        }
+        assert_eq!(glyph('é'), None);
+    }
+}
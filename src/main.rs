@@ -1,4 +1,5 @@
 use canvas::Svg;
+use draw::TextMode;
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
     execute,
@@ -9,13 +10,31 @@ use ratatui::{backend::CrosstermBackend, Terminal};
 use std::{fs::File, io, io::Write, sync::atomic::AtomicI32, time::Duration, vec, collections::HashMap};
 /// The index of the currently selected tab.
 static INDEX: AtomicI32 = AtomicI32::new(0);
+mod arc_length;
 mod canvas;
+mod clip;
+mod color;
+mod css;
 mod draw;
+mod error;
+mod fill;
+mod font;
+mod path;
+mod style;
+mod stroke;
 mod svg;
+mod transform;
 
 /// The main function.
 fn main() -> Result<(), io::Error> {
     let mut svgs = Svg::new();
+    // most terminal fonts draw cells about twice as tall as they are wide
+    svgs.cell_aspect(2.0);
+    svgs.tolerance(0.25);
+    // `--stroke-text` traces text with the stroke font instead of terminal characters
+    if std::env::args().any(|arg| arg == "--stroke-text") {
+        svgs.text_mode(TextMode::Strokes);
+    }
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
@@ -28,7 +47,29 @@ fn main() -> Result<(), io::Error> {
     svgs.add("A".to_string(), vec![r#"<path d="M 25.000 25.000 A 10.000 10.000 90.000 0 0 50.000 50.000" style="stroke: rgb(255, 0, 0); stroke-width: 1; fill: none;"/>"#.to_string(),]);
     svgs.add("AF1".to_string(), vec![r#"<path d="M 25.000 25.000 A 10.000 10.000 90.000 1 0 50.000 50.000" style="stroke: rgb(255, 0, 0); stroke-width: 1; fill: none;"/>"#.to_string(),]);
     svgs.add("AF2".to_string(), vec![r#"<path d="M 25.000 25.000 A 10.000 10.000 90.000 0 1 50.000 50.000" style="stroke: rgb(255, 0, 0); stroke-width: 1; fill: none;"/>"#.to_string(),]);
+    svgs.add("AF3".to_string(), vec![r#"<path d="M 25.000 25.000 A 10.000 10.000 90.000 1 1 50.000 50.000" style="stroke: rgb(255, 0, 0); stroke-width: 1; fill: none;"/>"#.to_string(),]);
+    svgs.add("Relative".to_string(), vec![r#"<path d="m 25.000 25.000 l 50.000 0.000 l 0.000 50.000 h -50.000 z m 10.000 10.000 v 30.000 h 30.000 z" style="stroke: rgb(255, 0, 0); stroke-width: 1; fill: none;"/>"#.to_string(),]);
+    svgs.add("Compact".to_string(), vec![r#"<path d="M25,25L75,25 75,75h-50zm10,10v30h30z" style="stroke: rgb(255, 0, 0); stroke-width: 1; fill: none;"/>"#.to_string(),]);
     svgs.add("Rect".to_string(), vec![r#"<rect x="25.000" y="25.000" width="50.000" height="50.000" style="stroke: rgb(255, 0, 0); stroke-width: 1; fill: none;"/>"#.to_string(),]);
+    svgs.add("Circle".to_string(), vec![r#"<circle cx="50.000" cy="50.000" r="25.000" style="stroke: rgb(255, 0, 0); stroke-width: 1; fill: none;"/>"#.to_string(),]);
+    svgs.add("Ellipse".to_string(), vec![r#"<ellipse cx="50.000" cy="50.000" rx="35.000" ry="15.000" transform="rotate(10)" style="stroke: rgb(255, 0, 0); stroke-width: 1; fill: none;"/>"#.to_string(),]);
+    svgs.add("Polygon".to_string(), vec![r#"<polygon points="50,15 61,40 88,40 66,56 74,82 50,66 26,82 34,56 12,40 39,40" style="stroke: rgb(255, 0, 0); stroke-width: 1; fill: none;"/>"#.to_string(),]);
+    svgs.add("Polyline".to_string(), vec![r#"<polyline points="10,80 20 60,30 70 40,75 50,40 60,55 70,20 80,35 90,25" style="stroke: rgb(255, 0, 0); stroke-width: 1; fill: none;"/>"#.to_string(),]);
+    svgs.add("ViewBox".to_string(), vec![r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-50,-50 100,100"><rect x="-25.000" y="-25.000" width="50.000" height="50.000" style="stroke: rgb(255, 0, 0); stroke-width: 1; fill: none;"/><line x1="0.000" y1="-50.000" x2="0.000" y2="50.000" style="stroke: rgb(0, 0, 255); stroke-width: 1; fill: none;"/></svg>"#.to_string(),]);
+    svgs.add("Slice".to_string(), vec![r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100" preserveAspectRatio="xMidYMin slice"><circle cx="50.000" cy="50.000" r="45.000" style="stroke: rgb(255, 0, 0); stroke-width: 1; fill: none;"/></svg>"#.to_string(),]);
+    svgs.add("Transform".to_string(), vec![r#"<rect x="30.000" y="30.000" width="40.000" height="40.000" transform="rotate(30 50 50)" style="stroke: rgb(255, 0, 0); stroke-width: 1; fill: none;"/>"#.to_string(), r#"<line x1="10.000" y1="50.000" x2="90.000" y2="50.000" transform="translate(0 30) skewX(20)" style="stroke: rgb(0, 0, 255); stroke-width: 1; fill: none;"/>"#.to_string(), r#"<path d="M 25.000 25.000 C 25.000 30.000 75.000 27.000 45.000 50.000" transform="scale(0.5) translate(50 50)" style="stroke: rgb(0, 255, 0); stroke-width: 1; fill: none;"/>"#.to_string(),]);
+    svgs.add("Group".to_string(), vec![r#"<g transform="rotate(-15 50 50)" style="stroke: rgb(255, 0, 0); stroke-width: 1; fill: none;"><rect x="20.000" y="30.000" width="60.000" height="40.000"/><g transform="translate(50 50)" style="stroke: rgb(0, 0, 255);"><circle cx="0.000" cy="0.000" r="10.000"/></g></g>"#.to_string(),]);
+    svgs.add("Colors".to_string(), vec![r##"<g fill="none" stroke-width="1"><circle cx="25.000" cy="30.000" r="15.000" stroke="#f80"/><circle cx="50.000" cy="30.000" r="15.000" stroke="hsl(200, 100%, 50%)"/><circle cx="75.000" cy="30.000" r="15.000" stroke="rgb(100% 0% 50% / 0.5)"/><g color="mediumseagreen"><rect x="20.000" y="60.000" width="60.000" height="20.000" stroke="currentColor"/></g></g>"##.to_string(),]);
+    svgs.add("Stylesheet".to_string(), vec![r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100"><style>path, rect { fill: none; stroke: #888 } .cls-1 { stroke: tomato } g .cls-1 { stroke: gold } #star { stroke: deepskyblue }</style><rect class="cls-1" x="10.000" y="10.000" width="30.000" height="30.000"/><g><rect class="cls-1" x="60.000" y="10.000" width="30.000" height="30.000"/></g><path id="star" d="M 50 55 L 59 80 L 35 65 L 65 65 L 41 80 Z"/></svg>"##.to_string(),]);
+    svgs.add("Fill".to_string(), vec![r#"<path d="M 25 10 L 36 45 L 7 23 L 43 23 L 14 45 Z" style="fill: rgb(255, 200, 0); stroke: rgb(255, 0, 0); fill-rule: nonzero;"/>"#.to_string(), r#"<path d="M 75 10 L 86 45 L 57 23 L 93 23 L 64 45 Z" style="fill: rgb(255, 200, 0); stroke: rgb(255, 0, 0); fill-rule: evenodd;"/>"#.to_string(), r#"<path d="M 10 55 H 90 V 90 H 10 Z M 30 65 V 80 H 70 V 65 Z" style="fill: rgb(0, 120, 255); stroke: rgb(255, 255, 255);"/>"#.to_string(),]);
+    svgs.add("Stroke".to_string(), vec![r#"<g fill="none" stroke="rgb(255, 0, 0)"><path d="M 10 20 L 50 40 L 90 20" stroke-width="0.2"/><path d="M 10 50 L 50 70 L 90 50" stroke-width="2"/><path d="M 10 80 L 50 95 L 90 80" stroke-width="6"/></g>"#.to_string(),]);
+    svgs.add("Caps".to_string(), vec![r#"<g fill="none" stroke="rgb(255, 0, 0)" stroke-width="6"><path d="M 15 15 H 45" stroke-linecap="butt"/><path d="M 15 35 H 45" stroke-linecap="round"/><path d="M 15 55 H 45" stroke-linecap="square"/><path d="M 60 30 L 75 10 L 90 30" stroke-linejoin="miter"/><path d="M 60 55 L 75 35 L 90 55" stroke-linejoin="round"/><path d="M 60 80 L 75 60 L 90 80" stroke-linejoin="bevel"/><path d="M 15 90 L 30 70 L 45 90" stroke-miterlimit="1"/></g>"#.to_string(),]);
+    svgs.add("Dash".to_string(), vec![r#"<g fill="none" stroke="rgb(0, 128, 255)" stroke-width="3"><path d="M 10 15 H 90" stroke-dasharray="10 5"/><path d="M 10 30 H 90" stroke-dasharray="10 5" stroke-dashoffset="5"/><path d="M 10 45 H 90" stroke-dasharray="0 8" stroke-linecap="round"/><rect x="15" y="60" width="70" height="30" stroke-dasharray="12 4 2 4"/></g>"#.to_string(),]);
+    svgs.add("Use".to_string(), vec![r##"<defs><path id="arrow" d="M 0 5 H 20 M 14 0 L 20 5 L 14 10" fill="none" stroke-width="2"/></defs><symbol id="star" viewBox="0 0 10 10"><polygon points="5,0 6.2,3.5 10,3.8 7,6.1 8,10 5,7.8 2,10 3,6.1 0,3.8 3.8,3.5"/></symbol><use href="#arrow" x="10" y="10" stroke="rgb(255, 0, 0)"/><use href="#arrow" transform="rotate(30 50 50)" x="40" y="40" stroke="rgb(0, 255, 0)"/><use href="#star" x="10" y="50" width="40" height="40" fill="rgb(255, 255, 0)"/><use href="#star" x="60" y="60" width="30" height="30" fill="rgb(0, 128, 255)"/>"##.to_string(),]);
+    svgs.add("Viewports".to_string(), vec![r#"<rect x="5" y="5" width="40" height="40" fill="none" stroke="rgb(128, 128, 128)"/><svg x="5" y="5" width="40" height="40" viewBox="0 0 10 10"><circle cx="5" cy="5" r="7" fill="rgb(255, 0, 0)"/></svg><rect x="55" y="5" width="40" height="20" fill="none" stroke="rgb(128, 128, 128)"/><svg x="55" y="5" width="40" height="20" viewBox="0 0 10 10" preserveAspectRatio="xMinYMid slice"><circle cx="5" cy="5" r="5" fill="rgb(0, 255, 0)"/></svg><rect x="5" y="55" width="90" height="40" fill="none" stroke="rgb(128, 128, 128)"/><svg x="5" y="55" width="90" height="40" viewBox="0 0 10 10" preserveAspectRatio="xMaxYMid meet"><polygon points="5,0 10,10 0,10" fill="rgb(0, 128, 255)"/></svg>"#.to_string(),]);
+    svgs.add("Text".to_string(), vec![r#"<rect x="10" y="10" width="80" height="30" fill="none" stroke="rgb(128, 128, 128)"/><text x="50" y="25" text-anchor="middle" dominant-baseline="middle" fill="rgb(255, 255, 255)">Centred <tspan font-weight="bold" fill="rgb(255, 0, 0)">label</tspan></text><line x1="10" y1="60" x2="90" y2="60" stroke="rgb(0, 128, 255)"/><text x="10" y="60" font-style="italic" fill="rgb(0, 255, 0)">start</text><text x="90" y="60" text-anchor="end" fill="rgb(255, 255, 0)">end<tspan dy="15" x="90">below</tspan></text>"#.to_string(),]);
+    svgs.add("Font".to_string(), vec![r#"<g fill="rgb(255, 255, 255)"><text x="10" y="20" font-size="12">Hello, 123!</text><text x="10" y="45" font-size="20" font-weight="bold" fill="rgb(255, 128, 0)">Bold</text><text x="60" y="45" font-size="10" font-style="italic" fill="rgb(0, 255, 128)">italic</text><text x="50" y="75" font-size="9" text-anchor="middle" transform="rotate(-20 50 75)" fill="rgb(0, 160, 255)">turned and <tspan font-size="14">scaled</tspan></text></g>"#.to_string(),]);
+    svgs.add("TextPath".to_string(), vec![r##"<defs><path id="wave" d="M 10 60 C 30 20 50 20 60 50 S 80 80 90 40"/></defs><use href="#wave" fill="none" stroke="rgb(80, 80, 80)"/><text font-size="8" fill="rgb(255, 255, 0)"><textPath href="#wave" startOffset="50%" text-anchor="middle">text along a <tspan fill="rgb(255, 0, 0)">curve</tspan></textPath></text>"##.to_string(),]);
     svgs.add(
         "Line".to_string(),
     vec![
@@ -39,7 +80,8 @@ fn main() -> Result<(), io::Error> {
 
     svgs.import("test.svg".to_string(), String::new());
     svgs.import("current.svg".to_string(), String::new());
-    svgs.initialize();
+    // a file that fails to load is reported by the check in `run`
+    svgs.initialize().ok();
     loop {
         read_input(&mut terminal, svgs.as_hashmap().to_owned()).ok();
         svgs.run(&mut terminal);
@@ -107,14 +149,19 @@ pub fn read_input(
                         KeyCode::Enter => {
                             let f = File::create("current.svg").unwrap();
                             let mut f = std::io::BufWriter::new(f);
-                            writeln!(f, "<?xml version=\"1.0\" encoding=\"utf-8\"?>").unwrap();
-                            writeln!(f, "<svg viewBox=\"0 0 100 100\" xmlns=\"http://www.w3.org/2000/svg\">").unwrap();
                             let svg_string = svgs
                                 .get(
                                     &svgs.keys().sorted().cloned().collect::<Vec<String>>()
                                         [INDEX.load(std::sync::atomic::Ordering::Relaxed) as usize],
                                 )
                                 .unwrap().to_owned();
+                            // loaded files are already complete documents
+                            if svg::is_document(&svg_string.concat()) {
+                                write!(f, "{}", svg_string.concat()).unwrap();
+                                return Ok(());
+                            }
+                            writeln!(f, "<?xml version=\"1.0\" encoding=\"utf-8\"?>").unwrap();
+                            writeln!(f, "<svg viewBox=\"0 0 100 100\" xmlns=\"http://www.w3.org/2000/svg\">").unwrap();
                             for i in &svg_string {
                                 writeln!(f, "{i}").unwrap();
                             }
@@ -0,0 +1,253 @@
+use std::fmt;
+
+/// A path data parse error and the byte offset in the `d` attribute it was found at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl PathError {
+    fn new(position: usize, message: impl Into<String>) -> PathError {
+        PathError {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid path data at {}: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for PathError {}
+
+/// reads one number starting at `pos`, returning it and the index just past it.
+/// follows the SVG number grammar, so `-` and a second `.` end the number
+pub fn scan_number(bytes: &[u8], pos: usize) -> Option<(f64, usize)> {
+    let mut i = pos;
+    if i < bytes.len() && (bytes[i] == b'+' || bytes[i] == b'-') {
+        i += 1;
+    }
+    let mut digits = false;
+    while i < bytes.len() && bytes[i].is_ascii_digit() {
+        i += 1;
+        digits = true;
+    }
+    if i < bytes.len() && bytes[i] == b'.' {
+        i += 1;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+            digits = true;
+        }
+    }
+    if !digits {
+        return None;
+    }
+    if i < bytes.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+        let mut j = i + 1;
+        if j < bytes.len() && (bytes[j] == b'+' || bytes[j] == b'-') {
+            j += 1;
+        }
+        if j < bytes.len() && bytes[j].is_ascii_digit() {
+            while j < bytes.len() && bytes[j].is_ascii_digit() {
+                j += 1;
+            }
+            i = j;
+        }
+    }
+    let number = std::str::from_utf8(&bytes[pos..i]).ok()?.parse::<f64>().ok()?;
+    Some((number, i))
+}
+
+fn skip_whitespace(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+        i += 1;
+    }
+    i
+}
+
+/// skips a `comma-wsp`: optional whitespace around at most one comma
+fn skip_separator(bytes: &[u8], i: usize) -> usize {
+    let i = skip_whitespace(bytes, i);
+    if i < bytes.len() && bytes[i] == b',' {
+        skip_whitespace(bytes, i + 1)
+    } else {
+        i
+    }
+}
+
+fn is_number_start(byte: u8) -> bool {
+    byte.is_ascii_digit() || byte == b'.' || byte == b'-' || byte == b'+'
+}
+
+/// split path data into commands and their arguments.
+/// repeated argument sets become their own command, so `L 1 2 3 4` yields two
+/// `L`s and extra pairs after `M`/`m` become `L`/`l` as the grammar specifies
+pub fn parse_path(d: &str) -> Result<Vec<(char, Vec<f64>)>, PathError> {
+    let mut segments = Vec::new();
+    parse_path_into(d, &mut segments)?;
+    Ok(segments)
+}
+
+/// like `parse_path`, but the commands read before an error are kept in
+/// `segments`, so a path in error can still be drawn up to it
+pub fn parse_path_into(d: &str, segments: &mut Vec<(char, Vec<f64>)>) -> Result<(), PathError> {
+    let bytes = d.as_bytes();
+    let mut i = skip_whitespace(bytes, 0);
+    while i < bytes.len() {
+        let command = d[i..].chars().next().unwrap_or_default();
+        let arg_count = match command.to_ascii_uppercase() {
+            'M' | 'L' | 'T' => 2,
+            'H' | 'V' => 1,
+            'C' => 6,
+            'S' | 'Q' => 4,
+            'A' => 7,
+            'Z' => 0,
+            _ => return Err(PathError::new(i, format!("expected a command, found `{command}`"))),
+        };
+        if segments.is_empty() && command != 'M' && command != 'm' {
+            return Err(PathError::new(i, "path data must start with a moveto"));
+        }
+        i = skip_whitespace(bytes, i + 1);
+
+        let mut command = command;
+        loop {
+            let mut args = Vec::with_capacity(arg_count);
+            for n in 0..arg_count {
+                if n > 0 {
+                    i = skip_separator(bytes, i);
+                }
+                if command.eq_ignore_ascii_case(&'A') && (n == 3 || n == 4) {
+                    // flags are a single digit and may be packed together, as in `a1 1 0 0010 10`
+                    match bytes.get(i) {
+                        Some(b'0') => args.push(0.0),
+                        Some(b'1') => args.push(1.0),
+                        _ => return Err(PathError::new(i, "expected an arc flag of `0` or `1`")),
+                    }
+                    i += 1;
+                } else {
+                    match scan_number(bytes, i) {
+                        Some((number, next)) => {
+                            args.push(number);
+                            i = next;
+                        }
+                        None => return Err(PathError::new(i, "expected a number")),
+                    }
+                }
+            }
+            segments.push((command, args));
+            if arg_count == 0 {
+                i = skip_whitespace(bytes, i);
+                break;
+            }
+
+            let next = skip_separator(bytes, i);
+            if next < bytes.len() && is_number_start(bytes[next]) {
+                i = next;
+                command = match command {
+                    'M' => 'L',
+                    'm' => 'l',
+                    command => command,
+                };
+            } else {
+                i = skip_whitespace(bytes, i);
+                if i < bytes.len() && bytes[i] == b',' {
+                    return Err(PathError::new(i, "unexpected `,` before a command"));
+                }
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(d: &str) -> Vec<(char, Vec<f64>)> {
+        parse_path(d).unwrap_or_else(|error| panic!("{d:?}: {error}"))
+    }
+
+    fn error_at(d: &str) -> usize {
+        parse_path(d).expect_err(d).position
+    }
+
+    #[test]
+    fn compact_data() {
+        assert_eq!(
+            commands("M10,20L30-40c.5.5 1e2 3-6-7"),
+            vec![
+                ('M', vec![10.0, 20.0]),
+                ('L', vec![30.0, -40.0]),
+                ('c', vec![0.5, 0.5, 100.0, 3.0, -6.0, -7.0]),
+            ]
+        );
+        // the same data cut short still reads up to where the curve runs out
+        assert_eq!(error_at("M10,20L30-40c.5.5 1e2 3"), 23);
+    }
+
+    #[test]
+    fn numbers() {
+        // a sign starts a new number, and so does a second decimal point
+        assert_eq!(commands("M1-2")[0].1, vec![1.0, -2.0]);
+        assert_eq!(commands("M-1+2")[0].1, vec![-1.0, 2.0]);
+        assert_eq!(commands("M.5.5")[0].1, vec![0.5, 0.5]);
+        assert_eq!(commands("M 0.5.5")[0].1, vec![0.5, 0.5]);
+        // exponents, with or without a sign
+        assert_eq!(commands("M1e2 1E-2")[0].1, vec![100.0, 0.01]);
+        assert_eq!(commands("M1.5e+1-2e1")[0].1, vec![15.0, -20.0]);
+        assert_eq!(commands("M 1 , 2")[0].1, vec![1.0, 2.0]);
+        assert_eq!(commands("  M\n1\t2  ")[0].1, vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn repeated_arguments_repeat_the_command() {
+        assert_eq!(
+            commands("M 0 0 L 1 2 3 4"),
+            vec![('M', vec![0.0, 0.0]), ('L', vec![1.0, 2.0]), ('L', vec![3.0, 4.0])]
+        );
+        // extra pairs after a moveto are linetos, absolute or relative like it
+        assert_eq!(
+            commands("M 0 0 1 1 m 2 2 3 3z"),
+            vec![
+                ('M', vec![0.0, 0.0]),
+                ('L', vec![1.0, 1.0]),
+                ('m', vec![2.0, 2.0]),
+                ('l', vec![3.0, 3.0]),
+                ('z', vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn compact_arc_flags() {
+        assert_eq!(
+            commands("M0 0a1 1 0 0010 10"),
+            vec![('M', vec![0.0, 0.0]), ('a', vec![1.0, 1.0, 0.0, 0.0, 0.0, 10.0, 10.0])]
+        );
+        assert_eq!(commands("M0 0A1,1,0,1,1,2,2")[1].1, vec![1.0, 1.0, 0.0, 1.0, 1.0, 2.0, 2.0]);
+        assert_eq!(error_at("M0 0a1 1 0 2 0 10 10"), 11);
+    }
+
+    #[test]
+    fn commands_before_an_error_are_kept() {
+        let mut segments = Vec::new();
+        assert_eq!(parse_path_into("M 0 0 L 10 10 L 20", &mut segments).map_err(|error| error.position), Err(18));
+        assert_eq!(segments, vec![('M', vec![0.0, 0.0]), ('L', vec![10.0, 10.0])]);
+    }
+
+    #[test]
+    fn errors_are_positioned() {
+        // an exponent with no digits is not part of the number
+        assert_eq!(error_at("M 1e 2"), 3);
+        assert_eq!(error_at("M 1,,2"), 4);
+        assert_eq!(error_at("M 1 2,"), 5);
+        assert_eq!(error_at("M 1 2 X"), 6);
+        assert_eq!(error_at("L 1 2"), 0);
+        assert_eq!(error_at("M 1"), 3);
+    }
+}
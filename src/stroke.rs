@@ -0,0 +1,336 @@
+use crate::fill::subpaths;
+use crate::style::{LineCap, LineJoin, Style};
+
+/// Builds the area covered by a stroke out of polygons that all wind the same
+/// way, so filling them with the nonzero rule paints their union.
+struct Outline {
+    points: Vec<(f64, f64, bool)>,
+    /// how far the sides of a round join or cap may stray from the true circle
+    tolerance: f64,
+}
+
+impl Outline {
+    fn polygon(&mut self, mut polygon: Vec<(f64, f64)>) {
+        let area = polygon
+            .iter()
+            .zip(polygon.iter().cycle().skip(1))
+            .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+            .sum::<f64>();
+        if area == 0.0 {
+            return;
+        }
+        if area < 0.0 {
+            polygon.reverse();
+        }
+        for (i, (x, y)) in polygon.into_iter().enumerate() {
+            self.points.push((x, y, i != 0));
+        }
+    }
+
+    fn circle(&mut self, center: (f64, f64), radius: f64) {
+        // a side spanning `step` radians bulges radius * (1 - cos(step / 2)) from the circle
+        let step = if self.tolerance < radius {
+            2.0 * (1.0 - self.tolerance / radius).acos()
+        } else {
+            std::f64::consts::PI
+        };
+        let n = ((2.0 * std::f64::consts::PI / step).ceil() as usize).max(4);
+        self.polygon(
+            (0..n)
+                .map(|i| {
+                    let angle = i as f64 / n as f64 * 2.0 * std::f64::consts::PI;
+                    (center.0 + radius * angle.cos(), center.1 + radius * angle.sin())
+                })
+                .collect(),
+        );
+    }
+}
+
+fn offset(point: (f64, f64), vector: (f64, f64), side: f64) -> (f64, f64) {
+    (point.0 + vector.0 * side, point.1 + vector.1 * side)
+}
+
+/// the direction from `a` to `b`, `length` long
+fn direction(a: (f64, f64), b: (f64, f64), length: f64) -> (f64, f64) {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let distance = dx.hypot(dy);
+    (dx / distance * length, dy / distance * length)
+}
+
+/// the left hand normal of the segment from `a` to `b`, `half_width` long
+fn normal(a: (f64, f64), b: (f64, f64), half_width: f64) -> (f64, f64) {
+    let (dx, dy) = direction(a, b, half_width);
+    (-dy, dx)
+}
+
+/// the join where the segment from `a` to `b` turns into the one from `b` to `c`
+fn join(outline: &mut Outline, (a, b, c): ((f64, f64), (f64, f64), (f64, f64)), style: &Style) {
+    let half_width = style.stroke_width / 2.0;
+    let (d1, d2) = (direction(a, b, 1.0), direction(b, c, 1.0));
+    let cross = d1.0 * d2.1 - d1.1 * d2.0;
+    let cos = d1.0 * d2.0 + d1.1 * d2.1;
+    if cross == 0.0 && cos > 0.0 {
+        // carrying straight on, the segments already meet
+        return;
+    }
+    // the outside of a left turn is on the right
+    let side = if cross > 0.0 { -1.0 } else { 1.0 };
+    let (n1, n2) = (normal(a, b, half_width), normal(b, c, half_width));
+    let bevel = vec![b, offset(b, n1, side), offset(b, n2, side)];
+    match style.stroke_linejoin {
+        LineJoin::Round => outline.circle(b, half_width),
+        LineJoin::Bevel => outline.polygon(bevel),
+        LineJoin::Miter => {
+            // the miter is 1 / cos(turn / 2) stroke widths long
+            let ratio = (2.0 / (1.0 + cos)).sqrt();
+            if cos <= -1.0 || ratio > style.stroke_miterlimit {
+                outline.polygon(bevel);
+            } else {
+                let tip = offset(b, ((n1.0 + n2.0) / (1.0 + cos), (n1.1 + n2.1) / (1.0 + cos)), side);
+                outline.polygon(vec![b, offset(b, n1, side), tip, offset(b, n2, side)]);
+            }
+        }
+    }
+}
+
+/// the cap at `end`, for a subpath arriving there from `from`
+fn cap(outline: &mut Outline, from: (f64, f64), end: (f64, f64), style: &Style) {
+    let half_width = style.stroke_width / 2.0;
+    match style.stroke_linecap {
+        LineCap::Butt => {}
+        LineCap::Round => outline.circle(end, half_width),
+        LineCap::Square => {
+            let n = normal(from, end, half_width);
+            let d = direction(from, end, half_width);
+            let beyond = offset(end, d, 1.0);
+            outline.polygon(vec![
+                offset(end, n, 1.0),
+                offset(beyond, n, 1.0),
+                offset(beyond, n, -1.0),
+                offset(end, n, -1.0),
+            ]);
+        }
+    }
+}
+
+/// split `points` into the dashes of `dasharray`, starting `offset` into the
+/// pattern. each dash starts with a pen up point, so the gaps are not drawn.
+/// the pattern starts again at every subpath. `closed` holds whether each
+/// subpath is closed, and is returned for the dashes, which are all open
+pub fn dash(
+    points: &[(f64, f64, bool)],
+    closed: &[bool],
+    dasharray: &[f64],
+    offset: f64,
+) -> (Vec<(f64, f64, bool)>, Vec<bool>) {
+    let total = dasharray.iter().sum::<f64>();
+    if dasharray.is_empty() || total <= 0.0 {
+        return (points.to_vec(), closed.to_vec());
+    }
+    // even entries are dashes and odd entries are gaps
+    let is_dash = |index: usize| index.is_multiple_of(2);
+    let mut dashed = Vec::new();
+    for subpath in subpaths(points) {
+        // find the entry the offset lands in and how much of it is left
+        let mut index = 0;
+        let mut left = dasharray[0];
+        let mut skip = offset.rem_euclid(total);
+        while skip > 0.0 {
+            if skip >= left {
+                skip -= left;
+                index = (index + 1) % dasharray.len();
+                left = dasharray[index];
+            } else {
+                left -= skip;
+                skip = 0.0;
+            }
+        }
+
+        if is_dash(index) {
+            dashed.push((subpath[0].0, subpath[0].1, false));
+        }
+        for segment in subpath.windows(2) {
+            let (mut a, b) = (segment[0], segment[1]);
+            let mut length = (b.0 - a.0).hypot(b.1 - a.1);
+            // every dash or gap that ends on this segment
+            while length >= left {
+                let t = left / length;
+                let point = (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+                // the end of a dash is drawn to, the end of a gap starts the next dash
+                dashed.push((point.0, point.1, is_dash(index)));
+                length -= left;
+                a = point;
+                index = (index + 1) % dasharray.len();
+                left = dasharray[index];
+            }
+            left -= length;
+            if is_dash(index) {
+                dashed.push((b.0, b.1, true));
+            }
+        }
+    }
+    (dashed, Vec::new())
+}
+
+/// the area covered by stroking `points` in `style`, whose stroke width is in
+/// the same units as the points, as polygons to be filled with the nonzero rule.
+/// a subpath is joined round to its start where `closed` says so, and
+/// otherwise capped at both ends. round joins and caps stay within `tolerance`
+/// of a true circle
+pub fn stroke_outline(
+    points: &[(f64, f64, bool)],
+    closed: &[bool],
+    style: &Style,
+    tolerance: f64,
+) -> Vec<(f64, f64, bool)> {
+    let mut outline = Outline { points: Vec::new(), tolerance };
+    let half_width = style.stroke_width / 2.0;
+    for (index, mut subpath) in subpaths(points).into_iter().enumerate() {
+        let zero_length = subpath.len() > 1;
+        // repeated points have no direction to offset along
+        subpath.dedup();
+        if subpath.len() < 2 {
+            // a subpath that goes nowhere still shows its caps, as a dot
+            if zero_length {
+                let point = subpath[0];
+                cap(&mut outline, (point.0 - 1.0, point.1), point, style);
+                cap(&mut outline, (point.0 + 1.0, point.1), point, style);
+            }
+            continue;
+        }
+        let closed = closed.get(index) == Some(&true) && subpath.len() > 2;
+        for segment in subpath.windows(2) {
+            let (a, b) = (segment[0], segment[1]);
+            let n = normal(a, b, half_width);
+            outline.polygon(vec![
+                offset(a, n, 1.0),
+                offset(b, n, 1.0),
+                offset(b, n, -1.0),
+                offset(a, n, -1.0),
+            ]);
+        }
+
+        for corner in subpath.windows(3) {
+            join(&mut outline, (corner[0], corner[1], corner[2]), style);
+        }
+        let n = subpath.len();
+        if closed {
+            join(&mut outline, (subpath[n - 2], subpath[0], subpath[1]), style);
+        } else {
+            cap(&mut outline, subpath[1], subpath[0], style);
+            cap(&mut outline, subpath[n - 2], subpath[n - 1], style);
+        }
+    }
+    outline.points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fill::{covers, scanline_fill};
+    use crate::style::FillRule;
+
+    fn stroke(points: &[(f64, f64, bool)], declarations: &str) -> Vec<(f64, f64, bool)> {
+        stroke_closed(points, &[], declarations)
+    }
+
+    fn stroke_closed(points: &[(f64, f64, bool)], closed: &[bool], declarations: &str) -> Vec<(f64, f64, bool)> {
+        let mut style = Style::default();
+        style.set_declarations(declarations);
+        scanline_fill(&stroke_outline(points, closed, &style, 0.01), FillRule::NonZero, 1.0)
+    }
+
+    const CORNER: [(f64, f64, bool); 3] = [(10.0, 10.0, true), (30.0, 10.0, true), (30.0, 30.0, true)];
+
+    #[test]
+    fn strokes_cover_their_width_around_the_line() {
+        let spans = stroke(&CORNER, "stroke-width: 4");
+        assert!(covers(&spans, 20.0, 11.5));
+        assert!(covers(&spans, 20.0, 8.5));
+        assert!(!covers(&spans, 20.0, 12.5));
+        assert!(covers(&spans, 31.5, 20.5));
+    }
+
+    #[test]
+    fn caps() {
+        let butt = stroke(&CORNER, "stroke-width: 4; stroke-linecap: butt");
+        assert!(!covers(&butt, 9.0, 10.5));
+        let square = stroke(&CORNER, "stroke-width: 4; stroke-linecap: square");
+        assert!(covers(&square, 8.2, 11.5));
+        assert!(covers(&square, 31.5, 31.5));
+        let round = stroke(&CORNER, "stroke-width: 4; stroke-linecap: round");
+        assert!(covers(&round, 8.2, 10.5));
+        assert!(!covers(&round, 8.2, 11.5));
+    }
+
+    #[test]
+    fn dashes_follow_the_arc_length() {
+        let line = [(0.0, 0.0, true), (10.0, 0.0, true), (10.0, 10.0, true)];
+        let dashed = dash(&line, &[], &[4.0, 2.0], 0.0);
+        let dashes = subpaths(&dashed.0);
+        assert_eq!(
+            dashes,
+            vec![
+                vec![(0.0, 0.0), (4.0, 0.0)],
+                vec![(6.0, 0.0), (10.0, 0.0)],
+                // the third dash turns the corner
+                vec![(10.0, 2.0), (10.0, 6.0)],
+                vec![(10.0, 8.0), (10.0, 10.0)],
+            ]
+        );
+        // an offset moves the pattern back along the path, wrapping around
+        let dashes = subpaths(&dash(&line, &[], &[4.0, 2.0], 1.0).0);
+        assert_eq!(dashes[0], vec![(0.0, 0.0), (3.0, 0.0)]);
+        let dashes = subpaths(&dash(&line, &[], &[4.0, 2.0], -1.0).0);
+        assert_eq!(dashes[0], vec![(1.0, 0.0), (5.0, 0.0)]);
+    }
+
+    #[test]
+    fn joins() {
+        // the outer corner of this right angle is at (32, 8)
+        let miter = stroke(&CORNER, "stroke-width: 4");
+        assert!(covers(&miter, 31.8, 8.5));
+        let bevel = stroke(&CORNER, "stroke-width: 4; stroke-linejoin: bevel");
+        assert!(!covers(&bevel, 31.8, 8.5));
+        assert!(covers(&bevel, 30.8, 9.5));
+        let round = stroke(&CORNER, "stroke-width: 4; stroke-linejoin: round");
+        assert!(!covers(&round, 31.8, 8.5));
+        assert!(covers(&round, 31.2, 8.5));
+        // a right angle miter is 1.41 stroke widths long
+        let limited = stroke(&CORNER, "stroke-width: 4; stroke-miterlimit: 1.4");
+        assert!(!covers(&limited, 31.8, 8.5));
+    }
+
+    #[test]
+    fn only_closed_subpaths_join_at_their_start() {
+        // back at the start, but open, so square caps stick out past the corner
+        let triangle = [(10.0, 10.0, true), (30.0, 10.0, true), (30.0, 30.0, true), (10.0, 10.0, true)];
+        let style = "stroke-width: 4; stroke-linecap: square; stroke-linejoin: bevel";
+        assert!(covers(&stroke_closed(&triangle, &[false], style), 8.5, 10.5));
+        // closed, the corner is bevelled like the others
+        assert!(!covers(&stroke_closed(&triangle, &[true], style), 8.5, 10.5));
+        // dashes are never closed
+        assert_eq!(dash(&triangle, &[true], &[4.0, 2.0], 0.0).1, Vec::<bool>::new());
+        assert_eq!(dash(&triangle, &[true], &[], 0.0).1, vec![true]);
+    }
+
+    #[test]
+    fn round_joins_follow_the_tolerance() {
+        let outline = |tolerance| {
+            let mut outline = Outline { points: Vec::new(), tolerance };
+            outline.circle((0.0, 0.0), 10.0);
+            outline.points
+        };
+        let (coarse, fine) = (outline(1.0), outline(0.01));
+        assert!(coarse.len() < fine.len());
+        // the middle of every side is within the tolerance of the circle
+        for (points, tolerance) in [(coarse, 1.0), (fine, 0.01)] {
+            for (a, b) in points.iter().zip(points.iter().cycle().skip(1)) {
+                let middle = ((a.0 + b.0) / 2.0).hypot((a.1 + b.1) / 2.0);
+                assert!(10.0 - middle <= tolerance + 1e-9, "{middle}");
+            }
+        }
+        // a circle smaller than the tolerance is still a dot
+        assert_eq!(outline(5.0).len(), 4);
+    }
+}
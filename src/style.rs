@@ -0,0 +1,470 @@
+use ratatui::style::{Color, Modifier};
+
+use crate::color::Rgba;
+
+/// The properties that can also be given as presentation attributes, such as `fill="none"`.
+pub const PROPERTIES: [&str; 15] = [
+    "color",
+    "fill",
+    "fill-rule",
+    "stroke",
+    "stroke-width",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "stroke-miterlimit",
+    "stroke-dasharray",
+    "stroke-dashoffset",
+    "font-size",
+    "font-weight",
+    "font-style",
+    "text-anchor",
+    "dominant-baseline",
+];
+
+/// What a `fill` or `stroke` is painted with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Paint {
+    None,
+    Color(Rgba),
+    /// the value of the `color` property
+    CurrentColor,
+}
+
+impl Paint {
+    /// parse `none`, `currentColor`, any CSS colour, or a `url(...)` reference.
+    /// gradients and patterns are not drawn, so a reference is painted with its
+    /// fallback, or not at all without one
+    pub fn parse(value: &str) -> Option<Paint> {
+        let value = value.trim();
+        if let Some(reference) = value.strip_prefix("url(") {
+            let (_, fallback) = reference.split_once(')')?;
+            let fallback = fallback.trim();
+            return match fallback {
+                "" => Some(Paint::None),
+                fallback if fallback.starts_with("url(") => None,
+                fallback => Paint::parse(fallback),
+            };
+        }
+        if value == "none" {
+            Some(Paint::None)
+        } else if value.eq_ignore_ascii_case("currentColor") {
+            Some(Paint::CurrentColor)
+        } else {
+            Rgba::parse(value).map(Paint::Color)
+        }
+    }
+}
+
+/// How the inside of a shape whose outline crosses itself is decided.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FillRule {
+    /// inside when the outline winds around the point a nonzero number of times
+    #[default]
+    NonZero,
+    /// inside when a ray from the point crosses the outline an odd number of times
+    EvenOdd,
+}
+
+impl FillRule {
+    /// is a point with this winding number inside
+    pub fn is_inside(&self, winding: i32) -> bool {
+        match self {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+}
+
+/// The shape drawn at the open ends of a stroke.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LineCap {
+    /// stop square at the end point
+    #[default]
+    Butt,
+    /// a half circle around the end point
+    Round,
+    /// a half square around the end point
+    Square,
+}
+
+/// The shape drawn where two segments of a stroke meet.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LineJoin {
+    /// extend the outer edges until they meet, unless that passes the miter limit
+    #[default]
+    Miter,
+    /// a circle around the corner
+    Round,
+    /// cut the corner off
+    Bevel,
+}
+
+/// Whether text is upright or slanted.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FontStyle {
+    #[default]
+    Normal,
+    /// italic or oblique, which a terminal draws the same
+    Italic,
+}
+
+/// Which end of a line of text its position is at.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TextAnchor {
+    #[default]
+    Start,
+    Middle,
+    End,
+}
+
+/// Which line across the characters the `y` of text is at.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DominantBaseline {
+    /// the bottom of the letters, the usual baseline
+    #[default]
+    Alphabetic,
+    /// halfway up the letters
+    Middle,
+    /// the top of the letters
+    Hanging,
+}
+
+/// The computed style of an element.
+/// every property here is inherited, so a child starts from a copy of its parent
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+    /// what `currentColor` refers to
+    pub color: Rgba,
+    pub fill: Paint,
+    pub fill_rule: FillRule,
+    pub stroke: Paint,
+    /// in user units
+    pub stroke_width: f64,
+    pub stroke_linecap: LineCap,
+    pub stroke_linejoin: LineJoin,
+    /// the longest a miter may be, as a multiple of the stroke width
+    pub stroke_miterlimit: f64,
+    /// alternating dash and gap lengths, always an even number of them.
+    /// empty for a solid stroke
+    pub stroke_dasharray: Vec<f64>,
+    /// how far into the dash pattern the stroke starts
+    pub stroke_dashoffset: f64,
+    /// in user units
+    pub font_size: f64,
+    /// from 1 to 1000, bold is 700
+    pub font_weight: u16,
+    pub font_style: FontStyle,
+    pub text_anchor: TextAnchor,
+    pub dominant_baseline: DominantBaseline,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            color: Rgba::new(0, 0, 0, 1.0),
+            fill: Paint::Color(Rgba::new(0, 0, 0, 1.0)),
+            fill_rule: FillRule::NonZero,
+            stroke: Paint::None,
+            stroke_width: 1.0,
+            stroke_linecap: LineCap::Butt,
+            stroke_linejoin: LineJoin::Miter,
+            stroke_miterlimit: 4.0,
+            stroke_dasharray: Vec::new(),
+            stroke_dashoffset: 0.0,
+            font_size: 16.0,
+            font_weight: 400,
+            font_style: FontStyle::Normal,
+            text_anchor: TextAnchor::Start,
+            dominant_baseline: DominantBaseline::Alphabetic,
+        }
+    }
+}
+
+impl Style {
+    /// set one property. like CSS, a value that does not parse is dropped and
+    /// the property keeps what it had, which is also how `inherit` behaves
+    pub fn set(&mut self, name: &str, value: &str) {
+        // there is no separate important layer in the cascade, so it is ignored
+        let value = value.trim().trim_end_matches("!important").trim_end();
+        if value == "inherit" {
+            return;
+        }
+        match name {
+            // `color: currentColor` is the same as inheriting
+            "color" => {
+                if let Some(color) = Rgba::parse(value) {
+                    self.color = color;
+                }
+            }
+            "fill" => {
+                if let Some(paint) = Paint::parse(value) {
+                    self.fill = paint;
+                }
+            }
+            "fill-rule" => match value {
+                "nonzero" => self.fill_rule = FillRule::NonZero,
+                "evenodd" => self.fill_rule = FillRule::EvenOdd,
+                _ => {}
+            },
+            "stroke" => {
+                if let Some(paint) = Paint::parse(value) {
+                    self.stroke = paint;
+                }
+            }
+            "stroke-width" => {
+                if let Ok(width) = value.trim_end_matches("px").parse::<f64>() {
+                    if width >= 0.0 {
+                        self.stroke_width = width;
+                    }
+                }
+            }
+            "stroke-linecap" => match value {
+                "butt" => self.stroke_linecap = LineCap::Butt,
+                "round" => self.stroke_linecap = LineCap::Round,
+                "square" => self.stroke_linecap = LineCap::Square,
+                _ => {}
+            },
+            "stroke-linejoin" => match value {
+                // the SVG 2 variants fall back to the closest SVG 1.1 join
+                "miter" | "miter-clip" | "arcs" => self.stroke_linejoin = LineJoin::Miter,
+                "round" => self.stroke_linejoin = LineJoin::Round,
+                "bevel" => self.stroke_linejoin = LineJoin::Bevel,
+                _ => {}
+            },
+            "stroke-miterlimit" => {
+                if let Ok(limit) = value.parse::<f64>() {
+                    if limit >= 1.0 {
+                        self.stroke_miterlimit = limit;
+                    }
+                }
+            }
+            "stroke-dasharray" => {
+                if value == "none" {
+                    self.stroke_dasharray = Vec::new();
+                    return;
+                }
+                let lengths = value
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|length| !length.is_empty())
+                    .map(|length| length.trim_end_matches("px").parse::<f64>().ok())
+                    .collect::<Option<Vec<f64>>>();
+                match lengths {
+                    Some(lengths) if lengths.iter().all(|length| *length >= 0.0) => {
+                        // an odd list is repeated, and a pattern with no length is solid
+                        self.stroke_dasharray = if lengths.iter().sum::<f64>() == 0.0 {
+                            Vec::new()
+                        } else if lengths.len() % 2 == 1 {
+                            lengths.repeat(2)
+                        } else {
+                            lengths
+                        };
+                    }
+                    _ => {}
+                }
+            }
+            "stroke-dashoffset" => {
+                if let Ok(offset) = value.trim_end_matches("px").parse::<f64>() {
+                    self.stroke_dashoffset = offset;
+                }
+            }
+            "font-size" => {
+                // the keywords are the CSS absolute sizes for a medium of 16px,
+                // and relative sizes scale the inherited size
+                let size = match value {
+                    "xx-small" => Some(9.0),
+                    "x-small" => Some(10.0),
+                    "small" => Some(13.0),
+                    "medium" => Some(16.0),
+                    "large" => Some(18.0),
+                    "x-large" => Some(24.0),
+                    "xx-large" => Some(32.0),
+                    "smaller" => Some(self.font_size / 1.2),
+                    "larger" => Some(self.font_size * 1.2),
+                    _ if value.ends_with('%') => {
+                        value.trim_end_matches('%').parse::<f64>().ok().map(|percent| self.font_size * percent / 100.0)
+                    }
+                    _ if value.ends_with("em") => {
+                        value.trim_end_matches("em").parse::<f64>().ok().map(|em| self.font_size * em)
+                    }
+                    _ if value.ends_with("pt") => value.trim_end_matches("pt").parse::<f64>().ok().map(|pt| pt * 4.0 / 3.0),
+                    _ => value.trim_end_matches("px").parse::<f64>().ok(),
+                };
+                if let Some(size) = size.filter(|size| *size >= 0.0) {
+                    self.font_size = size;
+                }
+            }
+            "font-weight" => {
+                // bolder and lighter step from the inherited weight the way CSS does
+                let weight = match value {
+                    "normal" => Some(400),
+                    "bold" => Some(700),
+                    "bolder" => Some(match self.font_weight {
+                        0..=349 => 400,
+                        350..=549 => 700,
+                        _ => 900,
+                    }),
+                    "lighter" => Some(match self.font_weight {
+                        0..=549 => 100,
+                        550..=749 => 400,
+                        _ => 700,
+                    }),
+                    _ => value.parse::<u16>().ok().filter(|weight| (1..=1000).contains(weight)),
+                };
+                if let Some(weight) = weight {
+                    self.font_weight = weight;
+                }
+            }
+            "font-style" => match value {
+                "normal" => self.font_style = FontStyle::Normal,
+                _ if value == "italic" || value.starts_with("oblique") => self.font_style = FontStyle::Italic,
+                _ => {}
+            },
+            "text-anchor" => match value {
+                "start" => self.text_anchor = TextAnchor::Start,
+                "middle" => self.text_anchor = TextAnchor::Middle,
+                "end" => self.text_anchor = TextAnchor::End,
+                _ => {}
+            },
+            "dominant-baseline" => match value {
+                "auto" | "alphabetic" | "ideographic" | "text-after-edge" | "text-bottom" => {
+                    self.dominant_baseline = DominantBaseline::Alphabetic
+                }
+                "middle" | "central" | "mathematical" => self.dominant_baseline = DominantBaseline::Middle,
+                "hanging" | "text-before-edge" | "text-top" => self.dominant_baseline = DominantBaseline::Hanging,
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    /// the colour a paint resolves to, or `None` when nothing is drawn
+    pub fn resolve(&self, paint: Paint) -> Option<Color> {
+        let color = match paint {
+            Paint::None => return None,
+            Paint::Color(color) => color,
+            Paint::CurrentColor => self.color,
+        };
+        (!color.is_transparent()).then(|| color.into())
+    }
+
+    pub fn fill_color(&self) -> Option<Color> {
+        self.resolve(self.fill)
+    }
+
+    pub fn stroke_color(&self) -> Option<Color> {
+        self.resolve(self.stroke)
+    }
+
+    /// how text is drawn in the terminal, in the fill colour or else the stroke
+    /// colour, or `None` when it has neither
+    pub fn text_style(&self) -> Option<ratatui::style::Style> {
+        let color = self.fill_color().or_else(|| self.stroke_color())?;
+        let mut style = ratatui::style::Style::default().fg(color);
+        if self.font_weight >= 600 {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if self.font_style == FontStyle::Italic {
+            style = style.add_modifier(Modifier::ITALIC);
+        }
+        Some(style)
+    }
+
+    /// the same style with its lengths multiplied by `factor`
+    pub fn scaled(&self, factor: f64) -> Style {
+        Style {
+            stroke_width: self.stroke_width * factor,
+            stroke_dasharray: self.stroke_dasharray.iter().map(|length| length * factor).collect(),
+            stroke_dashoffset: self.stroke_dashoffset * factor,
+            font_size: self.font_size * factor,
+            ..self.clone()
+        }
+    }
+
+    /// set every declaration of a `style` attribute, such as `fill: none; stroke: rgb(0, 0, 0)`
+    pub fn set_declarations(&mut self, declarations: &str) {
+        for declaration in declarations.split(';') {
+            if let Some((name, value)) = declaration.split_once(':') {
+                self.set(name.trim(), value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_fill_black_without_stroke() {
+        let style = Style::default();
+        assert_eq!(style.fill_color(), Some(Color::Rgb(0, 0, 0)));
+        assert_eq!(style.stroke, Paint::None);
+    }
+
+    #[test]
+    fn later_declarations_win_and_bad_values_are_dropped() {
+        let mut style = Style::default();
+        style.set_declarations("fill: none; stroke: rgb(1, 2, 3); stroke-width: 2px; fill: rgb(4, 5, 6)");
+        style.set_declarations("stroke: rgb(1, 2); stroke-width: -1; stroke: inherit");
+        assert_eq!(style.fill_color(), Some(Color::Rgb(4, 5, 6)));
+        assert_eq!(style.stroke_color(), Some(Color::Rgb(1, 2, 3)));
+        assert_eq!(style.stroke_width, 2.0);
+    }
+
+    #[test]
+    fn current_color_follows_the_color_property() {
+        let mut style = Style::default();
+        style.set_declarations("color: #00f; stroke: currentColor; fill: transparent");
+        assert_eq!(style.stroke_color(), Some(Color::Rgb(0, 0, 255)));
+        assert_eq!(style.fill_color(), None);
+    }
+
+    #[test]
+    fn references_paint_their_fallback() {
+        let mut style = Style::default();
+        style.set_declarations("fill: url(#gradient)");
+        assert_eq!(style.fill, Paint::None);
+        style.set_declarations("fill: url(#gradient) rgb(1, 2, 3); stroke: url('#p')  none");
+        assert_eq!(style.fill_color(), Some(Color::Rgb(1, 2, 3)));
+        assert_eq!(style.stroke, Paint::None);
+        style.set_declarations("stroke: url(#a) currentColor");
+        assert_eq!(style.stroke, Paint::CurrentColor);
+        // an invalid fallback drops the whole declaration
+        style.set_declarations("stroke: url(#a) url(#b); fill: url(#a) bogus; fill: url(#a");
+        assert_eq!(style.stroke, Paint::CurrentColor);
+        assert_eq!(style.fill_color(), Some(Color::Rgb(1, 2, 3)));
+    }
+
+    #[test]
+    fn text_styles() {
+        let mut style = Style::default();
+        style.set_declarations("font-weight: 500; font-weight: bolder; font-style: oblique 10deg");
+        style.set_declarations("fill: none; stroke: #f00; text-anchor: middle; dominant-baseline: central");
+        assert_eq!(style.font_weight, 700);
+        assert_eq!(style.text_anchor, TextAnchor::Middle);
+        assert_eq!(style.dominant_baseline, DominantBaseline::Middle);
+        let text = style.text_style().unwrap();
+        assert_eq!(text.fg, Some(Color::Rgb(255, 0, 0)));
+        assert!(text.add_modifier.contains(Modifier::BOLD | Modifier::ITALIC));
+        style.set("font-weight", "1001");
+        assert_eq!(style.font_weight, 700);
+        // relative sizes build on the inherited one
+        style.set_declarations("font-size: x-large; font-size: 50%");
+        assert_eq!(style.font_size, 12.0);
+        style.set_declarations("font-size: 1.5em; font-size: -2px");
+        assert_eq!(style.font_size, 18.0);
+        style.set("font-size", "12pt");
+        assert_eq!(style.font_size, 16.0);
+    }
+
+    #[test]
+    fn dash_arrays() {
+        let mut style = Style::default();
+        style.set("stroke-dasharray", "5, 2 1");
+        assert_eq!(style.stroke_dasharray, vec![5.0, 2.0, 1.0, 5.0, 2.0, 1.0]);
+        style.set("stroke-dasharray", "4 -1");
+        assert_eq!(style.stroke_dasharray.len(), 6);
+        style.set("stroke-dasharray", "0 0");
+        assert!(style.stroke_dasharray.is_empty());
+    }
+}
@@ -1,692 +1,2092 @@
 use std::collections::HashMap;
 
-use lazy_static::lazy_static;
-use regex::Regex;
+use ratatui::layout::Rect;
+use xml::attribute::OwnedAttribute;
+use xml::reader::XmlEvent;
 
-lazy_static! {
-/// A regex to match the fill attribute of an SVG path.
-/// to get [x, y, x] from fill: rgb(x, y, z);
-static ref FILL_RE: Regex = Regex::new(r"fill:\s*rgb\((\d+),\s*(\d+),\s*(\d+)\);").unwrap();}
+use crate::arc_length::ArcLength;
+use crate::canvas::{canvas_bounds, dot_size};
+use crate::css::{Element, Stylesheet};
+use crate::draw::{RenderOptions, TextMode};
+use crate::error::SvgError;
+use crate::font;
+use crate::path::{parse_path, parse_path_into, scan_number};
+use crate::style::{DominantBaseline, FontStyle, LineCap, LineJoin, Paint, Style, TextAnchor, PROPERTIES};
+use crate::transform::Transform;
 
-/// A type to hold the points, style, and if filled of an SVG path.
-pub type SvgPoints = (Vec<(f64, f64, bool)>, String, bool);
+/// A type to hold the points, style, if filled, clip polygons and closed subpaths of an SVG path.
+/// the points and the convex polygons of the viewports the shape is clipped to
+/// are in canvas units, and the stroke lengths of the style in root user units.
+/// the last holds whether each subpath was closed with `Z`
+pub type SvgPoints = (Vec<(f64, f64, bool)>, Style, bool, Vec<Vec<(f64, f64)>>, Vec<bool>);
 
-pub fn render_svg(svg: String, hash_map: &mut HashMap<usize, SvgPoints>) {
-    let mut view_box = Vec::new();
-    // read the whole file
-    let parser = xml::reader::EventReader::from_str(svg.as_str());
-    let mut objects = Vec::new();
-    for event in parser {
-        match event.unwrap() {
-            xml::reader::XmlEvent::StartElement {
-                name,
-                attributes,
-                namespace: _,
-            } => match name.local_name.as_str() {
-                "svg" => {
-                    let viewbox = &attributes[0].value;
-                    let viewbox_data = viewbox.split(' ').collect::<Vec<&str>>();
-                    let width = viewbox_data[2].parse::<f64>().unwrap();
-                    let height = viewbox_data[3].parse::<f64>().unwrap();
-                    view_box.push(width);
-                    view_box.push(height);
+/// The flattened points of a path and whether each subpath was closed with `Z`.
+type Path = (Vec<(f64, f64, bool)>, Vec<bool>);
+
+/// A run of characters from a `<text>` or `<tspan>`, drawn as terminal characters
+/// unless the text mode traces it with the stroke font.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgText {
+    pub text: String,
+    /// where an `x` or `y` starts a new chunk of text, in canvas units.
+    /// a run without one carries on after the previous run
+    pub position: Option<(f64, f64)>,
+    /// the `dx` and `dy` before the run, in canvas units
+    pub shift: (f64, f64),
+    pub style: Style,
+    /// the convex polygons of the viewports the text is clipped to
+    pub clip: Vec<Vec<(f64, f64)>>,
+    /// the transform from the user space of the run to the canvas. a character
+    /// on a `<textPath>` has its own, turned to follow the path
+    pub transform: Transform,
+}
+
+/// The region of user space that is stretched over the 100 by 100 canvas.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewBox {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Default for ViewBox {
+    fn default() -> Self {
+        ViewBox {
+            min_x: 0.0,
+            min_y: 0.0,
+            width: 100.0,
+            height: 100.0,
+        }
+    }
+}
+
+impl ViewBox {
+    /// the `viewBox` of an `<svg>`, falling back to its `width` and `height`
+    pub fn from_attributes(attributes: &[OwnedAttribute]) -> Result<ViewBox, SvgError> {
+        if let Some(view_box) = attribute(attributes, "viewBox") {
+            let numbers = parse_numbers(view_box).unwrap_or_default();
+            if numbers.len() != 4 || numbers[2] <= 0.0 || numbers[3] <= 0.0 {
+                return Err(SvgError::attribute("viewBox", view_box));
+            }
+            return Ok(ViewBox {
+                min_x: numbers[0],
+                min_y: numbers[1],
+                width: numbers[2],
+                height: numbers[3],
+            });
+        }
+        let mut view_box = ViewBox::default();
+        // percentages are relative to a viewport we don't know, so they keep the default
+        if let Some(width) = attribute(attributes, "width").filter(|w| !w.ends_with('%')) {
+            view_box.width = absolute_length("width", width)?;
+        }
+        if let Some(height) = attribute(attributes, "height").filter(|h| !h.ends_with('%')) {
+            view_box.height = absolute_length("height", height)?;
+        }
+        Ok(view_box)
+    }
+    /// user units per canvas unit horizontally
+    pub fn x_scale(&self) -> f64 {
+        self.width / 100.0
+    }
+    /// user units per canvas unit vertically
+    pub fn y_scale(&self) -> f64 {
+        self.height / 100.0
+    }
+    /// the transform that fits this view box into a `width` by `height`
+    /// viewport at the origin, the way `aspect_ratio` asks
+    pub fn fit(&self, aspect_ratio: PreserveAspectRatio, width: f64, height: f64) -> Transform {
+        let (mut x_scale, mut y_scale) = (width / self.width, height / self.height);
+        let (mut x, mut y) = (0.0, 0.0);
+        if let Some((x_align, y_align)) = aspect_ratio.align {
+            let scale = if aspect_ratio.slice {
+                x_scale.max(y_scale)
+            } else {
+                x_scale.min(y_scale)
+            };
+            (x_scale, y_scale) = (scale, scale);
+            x = (width - self.width * scale) * x_align.factor();
+            y = (height - self.height * scale) * y_align.factor();
+        }
+        Transform::translate(x, y)
+            .multiply(&Transform::scale(x_scale, y_scale))
+            .multiply(&Transform::translate(-self.min_x, -self.min_y))
+    }
+    /// the mapping from user space to the canvas, with the canvas y axis pointing up
+    pub fn matrix(&self) -> Transform {
+        Transform::new(1.0, 0.0, 0.0, -1.0, 0.0, 100.0)
+            .multiply(&Transform::scale(1.0 / self.x_scale(), 1.0 / self.y_scale()))
+            .multiply(&Transform::translate(-self.min_x, -self.min_y))
+    }
+}
+
+/// Where the view box sits inside the viewport along one axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Min,
+    Mid,
+    Max,
+}
+
+impl Align {
+    /// the share of the leftover space that goes before the view box
+    pub fn factor(&self) -> f64 {
+        match self {
+            Align::Min => 0.0,
+            Align::Mid => 0.5,
+            Align::Max => 1.0,
+        }
+    }
+}
+
+/// The `preserveAspectRatio` attribute.
+/// an `align` of `None` stretches the view box over the viewport
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PreserveAspectRatio {
+    pub align: Option<(Align, Align)>,
+    pub slice: bool,
+}
+
+impl Default for PreserveAspectRatio {
+    fn default() -> Self {
+        PreserveAspectRatio {
+            align: Some((Align::Mid, Align::Mid)),
+            slice: false,
+        }
+    }
+}
+
+impl PreserveAspectRatio {
+    /// the `preserveAspectRatio` of an element, or the default when it has none
+    pub fn from_attributes(attributes: &[OwnedAttribute]) -> Result<PreserveAspectRatio, SvgError> {
+        match attribute(attributes, "preserveAspectRatio") {
+            Some(value) => PreserveAspectRatio::parse(value),
+            None => Ok(PreserveAspectRatio::default()),
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<PreserveAspectRatio, SvgError> {
+        let invalid = || SvgError::attribute("preserveAspectRatio", value);
+        let mut parts = value.split_whitespace().peekable();
+        // `defer` only matters for <image>, which is not drawn
+        parts.next_if_eq(&"defer");
+        let align = match parts.next() {
+            Some("none") => None,
+            Some(align) if align.len() == 8 && align.is_ascii() => {
+                let axis = |name: &str| match name {
+                    "Min" => Ok(Align::Min),
+                    "Mid" => Ok(Align::Mid),
+                    "Max" => Ok(Align::Max),
+                    _ => Err(invalid()),
+                };
+                match (&align[..1], &align[4..5]) {
+                    ("x", "Y") => Some((axis(&align[1..4])?, axis(&align[5..])?)),
+                    _ => return Err(invalid()),
                 }
-                "path" => {
-                    let mut variables = ("".to_string(), "".to_string(), "".to_string());
-                    for i in &attributes {
-                        match i.name.local_name.as_str() {
-                            "d" => {
-                                variables.0 = i.value.to_owned();
-                            }
-                            "style" => {
-                                variables.1 = i.value.to_owned();
-                            }
-                            "transform" => {
-                                variables.2 = i.value.to_owned();
-                            }
-
-                            _ => {}
-                        }
+            }
+            _ => return Err(invalid()),
+        };
+        let slice = match parts.next() {
+            None | Some("meet") => false,
+            Some("slice") => true,
+            Some(_) => return Err(invalid()),
+        };
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(PreserveAspectRatio { align, slice })
+    }
+}
+
+/// What an element inherits from its ancestors.
+#[derive(Debug, Clone, Default)]
+pub struct GraphicsState {
+    /// the `transform` of the element composed with those of its ancestors
+    pub transform: Transform,
+    pub style: Style,
+    /// the width and height of the nearest viewport in user units, what percentages refer to
+    pub viewport: (f64, f64),
+    /// the nested viewports around the element as convex polygons on the canvas
+    pub clip: Vec<Vec<(f64, f64)>>,
+}
+
+impl GraphicsState {
+    /// the state of a child element with these attributes and the declaration
+    /// blocks of the stylesheet rules that match it, in cascade order
+    pub fn child(
+        &self,
+        attributes: &[OwnedAttribute],
+        rules: &[&str],
+    ) -> Result<GraphicsState, SvgError> {
+        let mut state = self.clone();
+        if let Some(transform) = attribute(attributes, "transform") {
+            state.transform = state.transform.multiply(&Transform::parse(transform)?);
+        }
+        // presentation attributes lose to stylesheets, which lose to the `style` attribute
+        for name in PROPERTIES {
+            if let Some(value) = attribute(attributes, name) {
+                state.style.set(name, value);
+            }
+        }
+        for declarations in rules {
+            state.style.set_declarations(declarations);
+        }
+        if let Some(style) = attribute(attributes, "style") {
+            state.style.set_declarations(style);
+        }
+        Ok(state)
+    }
+}
+
+/// is `svg` a whole document with an `<svg>` root, rather than a snippet of
+/// elements that still needs one around it. only the first element is looked at,
+/// so a snippet with a nested `<svg>` inside is still a snippet
+pub fn is_document(svg: &str) -> bool {
+    xml::reader::EventReader::from_str(svg)
+        .into_iter()
+        .find_map(|event| match event {
+            Ok(XmlEvent::StartElement { name, .. }) => Some(name.local_name == "svg"),
+            Err(_) => Some(false),
+            _ => None,
+        })
+        .unwrap_or(false)
+}
+
+/// render every shape into `hash_map` and all text into `texts`, returning the
+/// view box of the root `<svg>` and how it should be fitted into the viewport.
+/// curves are flattened finely enough for `layout`, as `options` asks.
+/// an element in error is left out with its children and the rest of the
+/// document is still drawn, only an error in the XML or the root fails it
+pub fn render_svg(
+    svg: String,
+    hash_map: &mut HashMap<usize, SvgPoints>,
+    texts: &mut Vec<SvgText>,
+    layout: Rect,
+    options: &RenderOptions,
+) -> Result<(ViewBox, PreserveAspectRatio), SvgError> {
+    render_document(svg, hash_map, texts, layout, options).map(|(view_box, aspect_ratio, _)| (view_box, aspect_ratio))
+}
+
+/// `render_svg`, also returning the errors of the elements that were left out
+fn render_document(
+    svg: String,
+    hash_map: &mut HashMap<usize, SvgPoints>,
+    texts: &mut Vec<SvgText>,
+    layout: Rect,
+    options: &RenderOptions,
+) -> Result<(ViewBox, PreserveAspectRatio, Vec<SvgError>), SvgError> {
+    // read the whole file
+    let events = xml::reader::EventReader::from_str(svg.as_str())
+        .into_iter()
+        .collect::<Result<Vec<XmlEvent>, _>>()?;
+    let document = Document::new(events);
+    let mut renderer = Renderer {
+        document: &document,
+        hash_map,
+        texts,
+        view_box: ViewBox::default(),
+        aspect_ratio: PreserveAspectRatio::default(),
+        flatness: 0.0,
+        layout,
+        options,
+        references: Vec::new(),
+        instances: 0,
+        errors: Vec::new(),
+    };
+    renderer.flatness = renderer.tolerance();
+    if let Some(root) = document.root() {
+        renderer.render(root, &mut Vec::new(), &GraphicsState::default())?;
+    }
+    Ok((renderer.view_box, renderer.aspect_ratio, renderer.errors))
+}
+
+/// The events of a whole document, indexed so any element can be found again.
+struct Document {
+    events: Vec<XmlEvent>,
+    /// a stylesheet applies to the whole document, even the elements before it
+    stylesheet: Stylesheet,
+    /// the start event of every element with an `id`, the first one wins
+    ids: HashMap<String, usize>,
+    /// the end event of every start event
+    ends: HashMap<usize, usize>,
+    /// the start event of the parent of every element that has one
+    parents: HashMap<usize, usize>,
+}
+
+impl Document {
+    fn new(events: Vec<XmlEvent>) -> Document {
+        let mut ids = HashMap::new();
+        let mut ends = HashMap::new();
+        let mut parents = HashMap::new();
+        // the start events of the open elements
+        let mut open: Vec<usize> = Vec::new();
+        for (index, event) in events.iter().enumerate() {
+            match event {
+                XmlEvent::StartElement { attributes, .. } => {
+                    if let Some(id) = attribute(attributes, "id") {
+                        ids.entry(id.to_string()).or_insert(index);
                     }
-                    objects.push(variables);
-                }
-                "line" => {
-                    let len = hash_map.keys().len();
-                    let mut variables = (
-                        "".to_string(),
-                        "".to_string(),
-                        "".to_string(),
-                        "".to_string(),
-                        "".to_string(),
-                    );
-                    for i in &attributes {
-                        match i.name.local_name.as_str() {
-                            "x1" => {
-                                variables.0 = i.value.to_owned();
-                            }
-                            "y1" => {
-                                variables.1 = i.value.to_owned();
-                            }
-                            "x2" => {
-                                variables.2 = i.value.to_owned();
-                            }
-                            "y2" => {
-                                variables.3 = i.value.to_owned();
-                            }
-                            "style" => {
-                                variables.4 = i.value.to_owned();
-                            }
-                            _ => {}
-                        }
+                    if let Some(parent) = open.last() {
+                        parents.insert(index, *parent);
                     }
-                    hash_map.insert(
-                        len + 1,
-                        (
-                            vec![
-                                (
-                                    variables.0.parse::<f64>().unwrap() / (view_box[0] / 100.0),
-                                    100.0
-                                        - variables.1.parse::<f64>().unwrap()
-                                            / (view_box[1] / 100.0),
-                                    true,
-                                ),
-                                (
-                                    variables.2.parse::<f64>().unwrap() / (view_box[0] / 100.0),
-                                    100.0
-                                        - variables.3.parse::<f64>().unwrap()
-                                            / (view_box[1] / 100.0),
-                                    true,
-                                ),
-                            ],
-                            variables.4,
-                            false,
-                        ),
-                    );
+                    open.push(index);
                 }
-                "rect" => {
-                    let len = hash_map.keys().len();
-                    let mut variables = (
-                        "".to_string(),
-                        "".to_string(),
-                        "".to_string(),
-                        "".to_string(),
-                        "".to_string(),
-                    );
-                    for i in &attributes {
-                        match i.name.local_name.as_str() {
-                            "x" => {
-                                variables.0 = i.value.to_owned();
-                            }
-                            "y" => {
-                                variables.1 = i.value.to_owned();
-                            }
-                            "width" => {
-                                variables.2 = i.value.to_owned();
-                            }
-                            "height" => {
-                                variables.3 = i.value.to_owned();
-                            }
-                            "style" => {
-                                variables.4 = i.value.to_owned();
-                            }
-                            _ => {}
-                        }
-                    }
-                    let x = variables.0.parse::<f64>().unwrap() / (view_box[0] / 100.0);
-                    let y = variables.1.parse::<f64>().unwrap() / (view_box[1] / 100.0);
-                    let width = variables.2.parse::<f64>().unwrap() / (view_box[0] / 100.0);
-                    let height = variables.3.parse::<f64>().unwrap() / (view_box[1] / 100.0);
-                    let style = variables.4.to_owned();
-                    hash_map.insert(
-                        len + 1,
-                        (
-                            vec![
-                                (x, 100.0 - y, true),
-                                (x + width, 100.0 - y, true),
-                                (x + width, 100.0 - (y + height), true),
-                                (x, 100.0 - (y + height), true),
-                                (x, 100.0 - y, true),
-                            ],
-                            variables.4,
-                            false,
-                        ),
-                    );
-
-                    if FILL_RE.is_match(&style) {
-                        let mut fill: Vec<(f64, f64, bool)> = Vec::new();
-                        for i in (x as usize)..(x + width) as usize {
-                            for j in (y as usize)..(y + height) as usize {
-                                fill.push((i as f64, j as f64, true));
-                            }
-                        }
-                        hash_map.insert(hash_map.len(), (fill, style, true));
+                XmlEvent::EndElement { .. } => {
+                    if let Some(start) = open.pop() {
+                        ends.insert(start, index);
                     }
                 }
                 _ => {}
-            },
-            xml::reader::XmlEvent::StartDocument {
-                version: _,
-                encoding: _,
-                standalone: _,
-            } => continue,
-            xml::reader::XmlEvent::EndDocument => continue,
-            xml::reader::XmlEvent::ProcessingInstruction { name: _, data: _ } => continue,
-            xml::reader::XmlEvent::EndElement { name: _ } => continue,
-            xml::reader::XmlEvent::CData(_) => continue,
-            xml::reader::XmlEvent::Comment(_) => continue,
-            xml::reader::XmlEvent::Characters(_) => continue,
-            xml::reader::XmlEvent::Whitespace(_) => continue,
-        }
-    }
-
-    for object in objects.iter().enumerate() {
-        let points = draw_path(
-            object.1 .0.to_owned(),
-            view_box.clone(),
-            Some(&object.1 .2),
-            &object.1 .1,
-        );
-        //mainstruct.data.log.push(format!("Points: {:?}", points));
-        let style = object.1 .1.to_owned();
+            }
+        }
+        Document {
+            stylesheet: stylesheet(&events),
+            events,
+            ids,
+            ends,
+            parents,
+        }
+    }
+
+    /// the element started by the event at `index`, as selectors see it
+    fn element(&self, index: usize) -> Option<Element> {
+        match &self.events[index] {
+            XmlEvent::StartElement { name, attributes, .. } => Some(Element {
+                name: name.local_name.clone(),
+                attributes: attributes.clone(),
+            }),
+            _ => None,
+        }
+    }
 
-        hash_map.insert(hash_map.len() + 1, (points.0, style.clone(), false));
-        if points.1.is_some() {
-            hash_map.insert(hash_map.len() + 1 + 1, (points.1.unwrap(), style, true));
+    /// the elements that contain the one at `index`, outermost first
+    fn ancestors(&self, index: usize) -> Vec<Element> {
+        let mut ancestors = Vec::new();
+        let mut current = index;
+        while let Some(parent) = self.parents.get(&current) {
+            ancestors.extend(self.element(*parent));
+            current = *parent;
         }
+        ancestors.reverse();
+        ancestors
+    }
+
+    /// the start events of the children of the element at `index`
+    fn children(&self, index: usize) -> Vec<usize> {
+        let mut children = Vec::new();
+        let mut child = index + 1;
+        while child < self.ends[&index] {
+            match self.ends.get(&child) {
+                Some(end) => {
+                    children.push(child);
+                    child = end + 1;
+                }
+                // text, comments and the like
+                None => child += 1,
+            }
+        }
+        children
+    }
+
+    /// the start event of the root element
+    fn root(&self) -> Option<usize> {
+        self.events
+            .iter()
+            .position(|event| matches!(event, XmlEvent::StartElement { .. }))
     }
 }
-/// set of points for the path and filled points
-pub type Points = (Vec<(f64, f64, bool)>, Option<Vec<(f64, f64, bool)>>);
-fn draw_path(
-    strings: String,
-    view_box: Vec<f64>,
-    transform_str: Option<&str>,
-    style: &str,
-) -> Points {
-    let mut points: Vec<(f64, f64, bool)> = Vec::new();
 
-    let x_scale = view_box[0] / 100.0;
-    let y_scale = view_box[1] / 100.0;
-
-    let mut start: Option<(f64, f64, bool)> = None;
-    let mut prev_point = (0.0, 0.0, true);
-    let mut prev_command = "";
-    let mut prev_match = "".to_string();
-    let split = strings.split(' ').collect::<Vec<&str>>();
-    let mut str_index = 0;
-    let mut string_groups: Vec<Vec<&str>> = vec![Vec::new()];
-    for i in split {
-        let j: Vec<char> = i.chars().collect();
-        if j.is_empty() {
-            panic!("Invalid SVG -> Space at end of path");
-        }
-        if j[0].is_alphabetic() {
-            if string_groups[str_index].is_empty() {
-                string_groups[str_index].push(i);
-            } else {
-                str_index += 1;
-                string_groups.push(Vec::new());
-                string_groups[str_index].push(i);
+/// Where the next run of characters of a `<text>` goes.
+#[derive(Debug, Default)]
+struct TextPen {
+    /// the last `x` and `y` given, in user units
+    position: (f64, f64),
+    /// where the next run starts a new chunk, in canvas units
+    start: Option<(f64, f64)>,
+    /// the `dx` and `dy` still to apply, in canvas units
+    shift: (f64, f64),
+    /// the runs from each `<textPath>`, as the range of their indices in `texts`
+    paths: Vec<(std::ops::Range<usize>, TextPath)>,
+}
+
+/// The path a `<textPath>` lays its characters along.
+#[derive(Debug)]
+struct TextPath {
+    /// the flattened path on the canvas
+    points: Vec<(f64, f64, bool)>,
+    /// how far along the path the characters start, in canvas units
+    start_offset: f64,
+}
+
+/// The most copies the `<use>` elements of a document may make between them,
+/// so references that fan out at every level cannot run away.
+const MAX_INSTANCES: usize = 10_000;
+
+/// Renders the elements of a document into `hash_map` and `texts`.
+struct Renderer<'a> {
+    document: &'a Document,
+    hash_map: &'a mut HashMap<usize, SvgPoints>,
+    texts: &'a mut Vec<SvgText>,
+    /// the view box of the root `<svg>` and how it is fitted into the viewport
+    view_box: ViewBox,
+    aspect_ratio: PreserveAspectRatio,
+    /// the flattening tolerance in canvas units, known once the root view box is
+    flatness: f64,
+    layout: Rect,
+    options: &'a RenderOptions,
+    /// the ids that the `<use>` elements being rendered refer to, so a reference
+    /// back to one of them is not followed forever
+    references: Vec<String>,
+    /// how many copies the `<use>` elements have made so far
+    instances: usize,
+    /// the errors of the elements left out so far
+    errors: Vec<SvgError>,
+}
+
+impl Renderer<'_> {
+    /// the flattening tolerance in canvas units for the current view box
+    fn tolerance(&self) -> f64 {
+        let bounds = canvas_bounds(
+            self.view_box,
+            self.aspect_ratio,
+            self.layout,
+            self.options.cell_aspect,
+        );
+        let (dot_width, dot_height) = dot_size(bounds, self.layout);
+        self.options.tolerance * dot_width.min(dot_height)
+    }
+
+    /// render the element at `index` and its children. `path` holds the
+    /// elements around it and `parent` the state it inherits.
+    /// as the spec asks, an element in error is left out and the document goes
+    /// on, unless it is the root
+    fn render(
+        &mut self,
+        index: usize,
+        path: &mut Vec<Element>,
+        parent: &GraphicsState,
+    ) -> Result<(), SvgError> {
+        let depth = path.len();
+        match self.render_element(index, path, parent) {
+            Err(error) if depth > 0 => {
+                path.truncate(depth);
+                self.errors.push(error);
+                Ok(())
             }
-        } else {
-            string_groups[str_index].push(i);
+            result => result,
         }
     }
-    for i in string_groups.into_iter() {
-        //mainstruct.data.log.push(format!("i: {:?}", i));
-        if i[0] == " " {
-            continue;
+
+    fn render_element(
+        &mut self,
+        index: usize,
+        path: &mut Vec<Element>,
+        parent: &GraphicsState,
+    ) -> Result<(), SvgError> {
+        let document = self.document;
+        let XmlEvent::StartElement { name, attributes, .. } = &document.events[index] else {
+            return Ok(());
+        };
+        path.extend(document.element(index));
+        let rules = document.stylesheet.matching(path);
+        let mut state = parent.child(attributes, &rules)?;
+        let ctm = self.view_box.matrix().multiply(&state.transform);
+        let mut children = true;
+        match name.local_name.as_str() {
+            // the root viewport is fitted into the layout by the canvas bounds
+            "svg" if path.len() == 1 => {
+                self.view_box = ViewBox::from_attributes(attributes)?;
+                self.aspect_ratio = PreserveAspectRatio::from_attributes(attributes)?;
+                self.flatness = self.tolerance();
+                state.viewport = (self.view_box.width, self.view_box.height);
+            }
+            "svg" => {
+                let x = length("x", attribute(attributes, "x"), state.viewport.0)?.unwrap_or(0.0);
+                let y = length("y", attribute(attributes, "y"), state.viewport.1)?.unwrap_or(0.0);
+                let width = length("width", attribute(attributes, "width"), state.viewport.0)?;
+                let height = length("height", attribute(attributes, "height"), state.viewport.1)?;
+                let (width, height) = (width.unwrap_or(state.viewport.0), height.unwrap_or(state.viewport.1));
+                // an empty viewport shows nothing
+                children = width > 0.0 && height > 0.0;
+                if children {
+                    state = self.viewport(&state, (x, y, width, height), attributes)?;
+                }
+            }
+            // a group only passes its state on to its children
+            "g" => {}
+            // only drawn where a <use> refers to them, or used by other elements
+            // for clipping, masking, markers and paint, which is not drawn yet
+            "defs" | "symbol" | "clipPath" | "mask" | "marker" | "pattern" | "linearGradient"
+            | "radialGradient" => children = false,
+            "use" => {
+                self.use_element(index, attributes, &state)?;
+                children = false;
+            }
+            "text" => {
+                self.text(index, path, &state)?;
+                children = false;
+            }
+            // embedded content cannot be drawn, but the rest of the document still is
+            name @ ("image" | "foreignObject" | "iframe") => return Err(SvgError::Unsupported(name.to_string())),
+            name => shape(self.hash_map, name, attributes, &state, &ctm, self.flatness)?,
         }
-        if i[0] == "Z" {
-            points.push(start.unwrap());
-            continue;
-        } else {
-            //println!("{:?}", data);
-            let command = i[0];
+        if children {
+            self.render_children(index, path, &state)?;
+        }
+        path.pop();
+        Ok(())
+    }
 
-            match command {
-                "M" => {
-                    let x = i[1].parse::<f64>().unwrap();
-                    let y = i[2].parse::<f64>().unwrap();
-                    let mut x = x / x_scale;
-                    let mut y = y / y_scale;
-                    if let Some(ts) = transform_str {
-                        let transformed_points: (f64, f64) = transform(x, y, ts, (x_scale, y_scale));
-                        x = transformed_points.0;
-                        y = transformed_points.1;
-                    } else {
-                        continue;
-                    }
+    fn render_children(
+        &mut self,
+        index: usize,
+        path: &mut Vec<Element>,
+        state: &GraphicsState,
+    ) -> Result<(), SvgError> {
+        for child in self.document.children(index) {
+            self.render(child, path, state)?;
+        }
+        Ok(())
+    }
+
+    /// add the characters of a `<text>` and its `<tspan>`s to `texts`, with white
+    /// space collapsed the way CSS does, to single spaces and none at either end
+    fn text(&mut self, index: usize, path: &mut Vec<Element>, state: &GraphicsState) -> Result<(), SvgError> {
+        let start = self.texts.len();
+        let mut pen = TextPen::default();
+        self.text_content(index, path, state, &mut pen)?;
+        let mut after_space = true;
+        for run in &mut self.texts[start..] {
+            let mut text = String::new();
+            for c in run.text.chars() {
+                if !c.is_whitespace() {
+                    text.push(c);
+                    after_space = false;
+                } else if !after_space {
+                    text.push(' ');
+                    after_space = true;
+                }
+            }
+            run.text = text;
+        }
+        if let Some(run) = self.texts[start..].iter_mut().rev().find(|run| !run.text.is_empty()) {
+            if run.text.ends_with(' ') {
+                run.text.pop();
+            }
+        }
+        // from the last so the ranges before stay where they are
+        for (runs, text_path) in pen.paths.into_iter().rev() {
+            let placed = self.along_path(&self.texts[runs.clone()], &text_path);
+            self.texts.splice(runs, placed);
+        }
+        if self.options.text_mode == TextMode::Strokes {
+            let runs = self.texts.split_off(start);
+            self.stroke_text(&runs)?;
+        }
+        Ok(())
+    }
 
-                    if start.is_none() {
-                        //println!("Start: {:?}", (x, 100.0 - y));
-                        start = Some((x, 100.0 - y, true));
-                        points.push(start.unwrap());
-                        prev_point = start.unwrap();
-                    } else {
-                        prev_point = (x, 100.0 - y, false);
-                        points.push((x, 100.0 - y, false));
+    /// lay the characters of `runs` out one by one along a `<textPath>`, each
+    /// turned to follow the path where its middle is. characters whose middle
+    /// is off either end of the path are dropped. as terminal characters each
+    /// takes up one cell along the path, traced they take the advance of the font
+    fn along_path(&self, runs: &[SvgText], text_path: &TextPath) -> Vec<SvgText> {
+        let measure = ArcLength::new(&text_path.points);
+        let bounds = canvas_bounds(self.view_box, self.aspect_ratio, self.layout, self.options.cell_aspect);
+        let (dot_width, dot_height) = dot_size(bounds, self.layout);
+        // a cell is two braille dots wide and four tall
+        let cell = (2.0 * dot_width, 4.0 * dot_height);
+        let mut glyphs = Vec::new();
+        let mut distance = text_path.start_offset;
+        for run in runs {
+            for c in run.text.chars() {
+                let advance = match self.options.text_mode {
+                    TextMode::Cells => {
+                        let along = distance.clamp(0.0, measure.length());
+                        let direction = measure.at(along).map_or((1.0, 0.0), |(_, direction)| direction);
+                        (cell.0 / direction.0.abs()).min(cell.1 / direction.1.abs())
                     }
+                    TextMode::Strokes => font::ADVANCE * run.style.font_size / font::EM * run.transform.mean_scale(),
+                };
+                glyphs.push((distance, advance, c, run));
+                distance += advance;
+            }
+        }
+        // the anchor moves the characters back along the path, not across it
+        let share = match runs.first().map(|run| run.style.text_anchor) {
+            Some(TextAnchor::Middle) => 0.5,
+            Some(TextAnchor::End) => 1.0,
+            _ => 0.0,
+        };
+        let back = (distance - text_path.start_offset) * share;
+        glyphs
+            .into_iter()
+            .filter_map(|(distance, advance, c, run)| {
+                let (middle, direction) = measure.at(distance - back + advance / 2.0)?;
+                let origin = (middle.0 - direction.0 * advance / 2.0, middle.1 - direction.1 * advance / 2.0);
+                // x runs along the path and y, which points down in user space, to its right
+                let scale = run.transform.mean_scale();
+                let (x, y) = (direction.0 * scale, direction.1 * scale);
+                Some(SvgText {
+                    text: c.to_string(),
+                    position: Some(origin),
+                    shift: (0.0, 0.0),
+                    style: Style {
+                        text_anchor: TextAnchor::Start,
+                        ..run.style.clone()
+                    },
+                    clip: run.clip.clone(),
+                    transform: Transform::new(x, y, y, -x, origin.0, origin.1),
+                })
+            })
+            .collect()
+    }
 
-                    prev_command = command;
-                    prev_match = format!("{}, {}, {}", command, x, 100.0 - y);
-                }
-                "L" => {
-                    let x = i[1].parse::<f64>().unwrap();
-                    let y = i[2].parse::<f64>().unwrap();
-                    let mut x = x / x_scale;
-                    let mut y = y / y_scale;
-                    if let Some(ts) = transform_str {
-                        let transformed_points = transform(x, y, ts, (x_scale, y_scale));
-                        x = transformed_points.0;
-                        y = transformed_points.1;
-                    } else {
-                        continue;
+    /// trace runs of text with the stroke font into `hash_map`. glyphs are laid
+    /// out along the x axis of the user space of their run, so they turn and
+    /// stretch with it, and every chunk is anchored as a whole
+    fn stroke_text(&mut self, runs: &[SvgText]) -> Result<(), SvgError> {
+        // the start, end and anchor of every chunk, and where each glyph starts
+        let mut chunks = Vec::new();
+        let mut glyphs = Vec::new();
+        let mut pen = (0.0, 0.0);
+        for (index, run) in runs.iter().enumerate() {
+            if run.position.is_some() || chunks.is_empty() {
+                pen = run.position.unwrap_or(pen);
+                chunks.push((pen, pen, run.style.text_anchor));
+            }
+            pen = (pen.0 + run.shift.0, pen.1 + run.shift.1);
+            let advance = font::ADVANCE * run.style.font_size / font::EM;
+            let step = (run.transform.a * advance, run.transform.b * advance);
+            for c in run.text.chars() {
+                glyphs.push((chunks.len() - 1, index, pen, c));
+                pen = (pen.0 + step.0, pen.1 + step.1);
+            }
+            if let Some(chunk) = chunks.last_mut() {
+                chunk.1 = pen;
+            }
+        }
+
+        for (chunk, index, origin, c) in glyphs {
+            // a character the font does not have leaves a gap
+            let Some(d) = font::glyph(c).filter(|d| !d.is_empty()) else {
+                continue;
+            };
+            let run = &runs[index];
+            let (start, end, anchor) = chunks[chunk];
+            let share = match anchor {
+                TextAnchor::Start => 0.0,
+                TextAnchor::Middle => 0.5,
+                TextAnchor::End => 1.0,
+            };
+            let origin = (origin.0 - (end.0 - start.0) * share, origin.1 - (end.1 - start.1) * share);
+            // the glyph grid has capitals 7 units tall on the baseline
+            let baseline = match run.style.dominant_baseline {
+                DominantBaseline::Alphabetic => 0.0,
+                DominantBaseline::Middle => 3.5,
+                DominantBaseline::Hanging => 7.0,
+            };
+            let size = run.style.font_size / font::EM;
+            let t = &run.transform;
+            // how much the run is scaled from the user space of the root
+            let scale = t.mean_scale() / self.view_box.matrix().mean_scale();
+            let mut transform = Transform::translate(origin.0, origin.1)
+                .multiply(&Transform::new(t.a, t.b, t.c, t.d, 0.0, 0.0))
+                .multiply(&Transform::scale(size, size));
+            if run.style.font_style == FontStyle::Italic {
+                transform = transform.multiply(&Transform::skew_x(-12.0));
+            }
+            let transform = transform.multiply(&Transform::translate(0.0, baseline));
+            let points = draw_path(d, &transform, self.flatness)?;
+            add_shape(self.hash_map, points, &glyph_state(run, scale), false);
+        }
+        Ok(())
+    }
+
+    /// add the character data of a `<text>` or `<tspan>` and of the `<tspan>`s in it
+    fn text_content(
+        &mut self,
+        index: usize,
+        path: &mut Vec<Element>,
+        state: &GraphicsState,
+        pen: &mut TextPen,
+    ) -> Result<(), SvgError> {
+        let document = self.document;
+        let XmlEvent::StartElement { name, attributes, .. } = &document.events[index] else {
+            return Ok(());
+        };
+        let ctm = self.view_box.matrix().multiply(&state.transform);
+        // only the first of a list of positions is used, the rest are per character
+        let first = |name| attribute(attributes, name).and_then(|value| parse_numbers(value)?.first().copied());
+        let (x, y) = (first("x"), first("y"));
+        // a <text> always starts a chunk, at the origin unless it says otherwise
+        if x.is_some() || y.is_some() || name.local_name == "text" {
+            pen.position = (x.unwrap_or(pen.position.0), y.unwrap_or(pen.position.1));
+            pen.start = Some(ctm.apply(pen.position.0, pen.position.1));
+        }
+        let (dx, dy) = (first("dx").unwrap_or(0.0), first("dy").unwrap_or(0.0));
+        // a shift is a distance, so the translation of the transform does not apply
+        pen.shift.0 += ctm.a * dx + ctm.c * dy;
+        pen.shift.1 += ctm.b * dx + ctm.d * dy;
+
+        let mut child = index + 1;
+        while child < document.ends[&index] {
+            match &document.events[child] {
+                XmlEvent::StartElement { name, attributes, .. } => {
+                    if matches!(name.local_name.as_str(), "tspan" | "a") {
+                        path.extend(document.element(child));
+                        let state = state.child(attributes, &document.stylesheet.matching(path))?;
+                        self.text_content(child, path, &state, pen)?;
+                        path.pop();
+                    } else if name.local_name == "textPath" {
+                        path.extend(document.element(child));
+                        let state = state.child(attributes, &document.stylesheet.matching(path))?;
+                        // a reference to anything but a path draws nothing
+                        if let Some(text_path) = self.text_path(attributes, &ctm)? {
+                            let first = self.texts.len();
+                            self.text_content(child, path, &state, pen)?;
+                            pen.paths.push((first..self.texts.len(), text_path));
+                        }
+                        path.pop();
                     }
-                    points.push((x, 100.0 - y, true));
-                    prev_point = (x, 100.0 - y, true);
-                    prev_command = command;
-                    prev_match = format!("{}, {}, {}", command, x, 100.0 - y);
+                    child = document.ends[&child] + 1;
                 }
-                "Q" => {
-                    // Quadratic Bezier Curve
-
-                    let control_point_x = i[1].parse::<f64>().unwrap() / x_scale;
-                    let control_point_y = 100.0 - i[2].parse::<f64>().unwrap() / y_scale;
-                    let end_point_x = i[3].parse::<f64>().unwrap() / x_scale;
-                    let end_point_y = 100.0 - i[4].parse::<f64>().unwrap() / y_scale;
-                    for i in 0..100 {
-                        let t = i as f64 / 100.0;
-                        let point = quadratic_bezier_curve(
-                            &prev_point,
-                            &(control_point_x, control_point_y),
-                            &(end_point_x, end_point_y),
-                            t,
-                            transform_str,
-                            (x_scale, y_scale),
-                        );
-                        points.push(point);
-                    }
-                    prev_point = (end_point_x, end_point_y, true);
-                    prev_command = command;
-                    prev_match = format!(
-                        "{command}, {control_point_x}, {control_point_y}, {end_point_x}, {end_point_y}"
-                    );
+                XmlEvent::Characters(text) | XmlEvent::CData(text) | XmlEvent::Whitespace(text) => {
+                    self.texts.push(SvgText {
+                        text: text.clone(),
+                        position: pen.start.take(),
+                        shift: std::mem::take(&mut pen.shift),
+                        style: state.style.clone(),
+                        clip: state.clip.clone(),
+                        transform: ctm,
+                    });
+                    child += 1;
                 }
-                "C" => {
-                    // Cubic Bezier Curve
-                    let control_point_1_x = i[1].parse::<f64>().unwrap() / x_scale;
-                    let control_point_1_y = i[2].parse::<f64>().unwrap() / y_scale;
-                    let control_point_2_x = i[3].parse::<f64>().unwrap() / x_scale;
-                    let control_point_2_y = i[4].parse::<f64>().unwrap() / y_scale;
-                    let end_point_x = i[5].parse::<f64>().unwrap() / x_scale;
-                    let end_point_y = i[6].parse::<f64>().unwrap() / y_scale;
-                    for i in 0..100 {
-                        let t = i as f64 / 100.0;
-                        let point = cubic_bezier_curve(
-                            &prev_point,
-                            &(control_point_1_x, control_point_1_y),
-                            &(control_point_2_x, control_point_2_y),
-                            &(end_point_x, end_point_y),
-                            t,
-                            transform_str,
-                            (x_scale, y_scale),
-                        );
-                        points.push(point);
+                _ => child += 1,
+            }
+        }
+        Ok(())
+    }
+
+    /// the path a `<textPath>` refers to, flattened onto the canvas in the user
+    /// space of the text, along with its `startOffset`
+    fn text_path(&self, attributes: &[OwnedAttribute], ctm: &Transform) -> Result<Option<TextPath>, SvgError> {
+        let document = self.document;
+        let Some(target) = href(attributes).and_then(|id| document.ids.get(id)) else {
+            return Ok(None);
+        };
+        let XmlEvent::StartElement { name, attributes: target, .. } = &document.events[*target] else {
+            return Ok(None);
+        };
+        let Some(d) = attribute(target, "d").filter(|_| name.local_name == "path") else {
+            return Ok(None);
+        };
+        // the transform of the path is applied on top of the user space of the text
+        let ctm = match attribute(target, "transform") {
+            Some(transform) => ctm.multiply(&Transform::parse(transform)?),
+            None => *ctm,
+        };
+        let (points, _) = draw_path(d, &ctm, self.flatness)?;
+        // a percentage is of the length of the path, and a length is in user units
+        let scale = ctm.mean_scale();
+        let path_length = ArcLength::new(&points).length();
+        let start_offset = length("startOffset", attribute(attributes, "startOffset"), path_length / scale)?;
+        Ok(Some(TextPath {
+            points,
+            start_offset: start_offset.unwrap_or(0.0) * scale,
+        }))
+    }
+
+    /// the state inside a new viewport at `x`, `y` of `width` by `height` user units,
+    /// showing the `viewBox` of `attributes` the way their `preserveAspectRatio` asks.
+    /// what is drawn inside is clipped to the viewport unless `overflow` is visible
+    fn viewport(
+        &self,
+        state: &GraphicsState,
+        (x, y, width, height): (f64, f64, f64, f64),
+        attributes: &[OwnedAttribute],
+    ) -> Result<GraphicsState, SvgError> {
+        let mut state = state.clone();
+        if !matches!(attribute(attributes, "overflow"), Some("visible" | "auto")) {
+            let ctm = self.view_box.matrix().multiply(&state.transform);
+            let corners = [(x, y), (x + width, y), (x + width, y + height), (x, y + height)];
+            state.clip.push(corners.iter().map(|(x, y)| ctm.apply(*x, *y)).collect());
+        }
+        state.transform = state.transform.multiply(&Transform::translate(x, y));
+        state.viewport = (width, height);
+        // without a view box user units stay the same size
+        if attribute(attributes, "viewBox").is_some() {
+            let view_box = ViewBox::from_attributes(attributes)?;
+            let aspect_ratio = PreserveAspectRatio::from_attributes(attributes)?;
+            state.transform = state.transform.multiply(&view_box.fit(aspect_ratio, width, height));
+            state.viewport = (view_box.width, view_box.height);
+        }
+        Ok(state)
+    }
+
+    /// render a copy of the element a `<use>` refers to, moved by its `x` and `y`.
+    /// the copy inherits from the `<use>`, but stylesheets still match it where it was
+    fn use_element(
+        &mut self,
+        index: usize,
+        attributes: &[OwnedAttribute],
+        state: &GraphicsState,
+    ) -> Result<(), SvgError> {
+        let document = self.document;
+        let Some((id, target)) = href(attributes).and_then(|id| Some((id, *document.ids.get(id)?))) else {
+            // a reference to nothing, or to another file, draws nothing
+            return Ok(());
+        };
+        // a <use> inside what it refers to, or a chain of them leading back, would never end
+        let inside = target <= index && index <= document.ends[&target];
+        if inside || self.references.iter().any(|reference| reference == id) {
+            return Ok(());
+        }
+        self.instances += 1;
+        if self.instances > MAX_INSTANCES {
+            return Err(SvgError::Instances(MAX_INSTANCES));
+        }
+        self.references.push(id.to_string());
+        // the reference is done with whether or not the copy could be drawn
+        let copied = self.copy(target, attributes, state);
+        self.references.pop();
+        copied
+    }
+
+    /// draw a copy of the element at `target` for a `<use>` with `attributes`
+    fn copy(&mut self, target: usize, attributes: &[OwnedAttribute], state: &GraphicsState) -> Result<(), SvgError> {
+        let document = self.document;
+        let viewport = state.viewport;
+        let x = length("x", attribute(attributes, "x"), viewport.0)?.unwrap_or(0.0);
+        let y = length("y", attribute(attributes, "y"), viewport.1)?.unwrap_or(0.0);
+        let mut path = document.ancestors(target);
+        match &document.events[target] {
+            XmlEvent::StartElement { name, attributes: symbol, .. } if name.local_name == "symbol" => {
+                // a symbol is a new viewport, sized by the <use> or else by the symbol itself
+                path.extend(document.element(target));
+                let state = state.child(symbol, &document.stylesheet.matching(&path))?;
+                let size = |name: &str, reference: f64| {
+                    match length(name, attribute(attributes, name), reference)? {
+                        Some(size) => Ok(Some(size)),
+                        None => length(name, attribute(symbol, name), reference),
                     }
-                    prev_point = (end_point_x, end_point_y, true);
-                    prev_command = command;
-                    prev_match = format!(
-                        "{command}, {control_point_1_x}, {control_point_1_y}, {control_point_2_x}, {control_point_2_y}, {end_point_x}, {end_point_y}"
-                    );
+                };
+                let width = size("width", viewport.0)?.unwrap_or(viewport.0);
+                let height = size("height", viewport.1)?.unwrap_or(viewport.1);
+                if width > 0.0 && height > 0.0 {
+                    let state = self.viewport(&state, (x, y, width, height), symbol)?;
+                    self.render_children(target, &mut path, &state)?;
                 }
-                "S" => {
-                    let control_point_1_x: f64;
-                    let control_point_1_y: f64;
-                    let whitelist = vec!["C", "S"];
-                    if whitelist.contains(&prev_command) {
-                        // first control point is reflection of second control point on the previous command relative to the current point
-                        let data = prev_match.split(", ").collect::<Vec<&str>>();
-                        //mainstruct.data.log.push(format!("data: {:?}", data));
-                        control_point_1_x =
-                            prev_point.0 + (prev_point.0 - data[3].parse::<f64>().unwrap());
-                        control_point_1_y =
-                            prev_point.1 + (prev_point.1 - data[4].parse::<f64>().unwrap());
-                    } else {
-                        control_point_1_x = prev_point.0;
-                        control_point_1_y = prev_point.1;
-                    }
-                    // Smooth Cubic Bezier Curve
-                    let control_point_2_x = i[1].parse::<f64>().unwrap() / x_scale;
-                    let control_point_2_y = i[2].parse::<f64>().unwrap() / y_scale;
-                    let end_point_x = i[3].parse::<f64>().unwrap() / x_scale;
-                    let end_point_y = i[4].parse::<f64>().unwrap() / y_scale;
-                    for i in 0..100 {
-                        let t = i as f64 / 100.0;
-                        let point = cubic_bezier_curve(
-                            &prev_point,
-                            &(control_point_1_x, control_point_1_y),
-                            &(control_point_2_x, control_point_2_y),
-                            &(end_point_x, end_point_y),
-                            t,
-                            transform_str,
-                            (x_scale, y_scale),
-                        );
-                        points.push(point);
-                    }
-                    prev_point = (end_point_x, end_point_y, true);
-                    prev_command = command;
+            }
+            _ => {
+                let mut state = state.clone();
+                state.transform = state.transform.multiply(&Transform::translate(x, y));
+                self.render(target, &mut path, &state)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// add the outline of a basic shape or path to `hash_map`.
+/// any other element draws nothing
+fn shape(
+    hash_map: &mut HashMap<usize, SvgPoints>,
+    name: &str,
+    attributes: &[OwnedAttribute],
+    state: &GraphicsState,
+    ctm: &Transform,
+    flatness: f64,
+) -> Result<(), SvgError> {
+    match name {
+        "path" => {
+            // path data in error is still drawn up to the error
+            let mut segments = Vec::new();
+            let parsed = parse_path_into(attribute(attributes, "d").unwrap_or_default(), &mut segments);
+            add_shape(hash_map, flatten_path(segments, ctm, flatness), state, true);
+            parsed?;
+        }
+        "line" => {
+            let (width, height) = state.viewport;
+            let x1 = length("x1", attribute(attributes, "x1"), width)?.unwrap_or(0.0);
+            let y1 = length("y1", attribute(attributes, "y1"), height)?.unwrap_or(0.0);
+            let x2 = length("x2", attribute(attributes, "x2"), width)?.unwrap_or(0.0);
+            let y2 = length("y2", attribute(attributes, "y2"), height)?.unwrap_or(0.0);
+            let d = format!("M {x1} {y1} L {x2} {y2}");
+            // a line has no inside, so it is never filled
+            add_shape(hash_map, draw_path(&d, ctm, flatness)?, state, false);
+        }
+        "rect" => {
+            let viewport = state.viewport;
+            let x = length("x", attribute(attributes, "x"), viewport.0)?.unwrap_or(0.0);
+            let y = length("y", attribute(attributes, "y"), viewport.1)?.unwrap_or(0.0);
+            let width = length("width", attribute(attributes, "width"), viewport.0)?.unwrap_or(0.0);
+            let height = length("height", attribute(attributes, "height"), viewport.1)?.unwrap_or(0.0);
+            // a negative size is an error and a zero one disables rendering
+            for (name, size) in [("width", width), ("height", height)] {
+                if size < 0.0 {
+                    return Err(SvgError::attribute(name, &size.to_string()));
                 }
-                "T" => {
-                    // Smooth Quadratic Bezier Curve
-                    let control_point_x: f64;
-                    let control_point_y: f64;
-                    let whitelist = vec!["Q", "T"];
-                    if whitelist.contains(&prev_command) {
-                        // first control point is reflection of second control point on the previous command relative to the current point
-                        let data = prev_match.split(", ").collect::<Vec<&str>>();
-                        control_point_x =
-                            prev_point.0 + (prev_point.0 - data[1].parse::<f64>().unwrap());
-                        control_point_y =
-                            prev_point.1 + (prev_point.1 - data[2].parse::<f64>().unwrap());
-                    } else {
-                        control_point_x = prev_point.0;
-                        control_point_y = prev_point.1;
-                    }
-                    let end_point_x = i[1].parse::<f64>().unwrap() / x_scale;
-                    let end_point_y = i[2].parse::<f64>().unwrap() / y_scale;
-                    for i in 0..100 {
-                        let t = i as f64 / 100.0;
-                        let point = quadratic_bezier_curve(
-                            &prev_point,
-                            &(control_point_x, control_point_y),
-                            &(end_point_x, end_point_y),
-                            t,
-                            transform_str,
-                            (x_scale, y_scale),
-                        );
-                        points.push(point);
-                    }
-                    prev_point = (end_point_x, end_point_y, true);
-                    prev_command = command;
+            }
+            if width == 0.0 || height == 0.0 {
+                return Ok(());
+            }
+            let d = format!("M {x} {y} H {} V {} H {x} Z", x + width, y + height);
+            add_shape(hash_map, draw_path(&d, ctm, flatness)?, state, true);
+        }
+        "circle" | "ellipse" => {
+            let (width, height) = state.viewport;
+            let cx = length("cx", attribute(attributes, "cx"), width)?.unwrap_or(0.0);
+            let cy = length("cy", attribute(attributes, "cy"), height)?.unwrap_or(0.0);
+            let (rx, ry) = if name == "circle" {
+                // a percentage radius is of the diagonal of the viewport, normalised
+                let diagonal = ((width * width + height * height) / 2.0).sqrt();
+                let r = length("r", attribute(attributes, "r"), diagonal)?.unwrap_or(0.0);
+                (r, r)
+            } else {
+                // a missing radius is `auto`, which takes the other radius
+                let radius = |name, reference| match attribute(attributes, name) {
+                    Some(value) if value.trim() == "auto" => Ok(None),
+                    value => length(name, value, reference),
+                };
+                match (radius("rx", width)?, radius("ry", height)?) {
+                    (None, None) => (0.0, 0.0),
+                    (Some(rx), None) => (rx, rx),
+                    (None, Some(ry)) => (ry, ry),
+                    (Some(rx), Some(ry)) => (rx, ry),
                 }
-                "A" => {
-                    // Elliptical Arc
-                    let rx = i[1].parse::<f64>().unwrap() / x_scale;
-                    let ry = i[2].parse::<f64>().unwrap() / y_scale;
-                    let x_axis_rotation = i[3].parse::<f64>().unwrap();
-                    let large_arc_flag = i[4].parse::<i8>().unwrap() == 1;
-                    let sweep_flag = i[5].parse::<i8>().unwrap() == 1;
-                    let end_point_x = i[6].parse::<f64>().unwrap() / x_scale;
-                    let end_point_y = i[7].parse::<f64>().unwrap() / y_scale;
-
-                    let mut arc_points: Vec<(f64, f64, bool)> = elliptical_arc(
-                        prev_point,
-                        (rx, ry),
-                        x_axis_rotation,
-                        large_arc_flag,
-                        sweep_flag,
-                        (end_point_x, end_point_y),
-                        100,
-                    );
-                    points.append(&mut arc_points);
-                    prev_point = (end_point_x, end_point_y, true);
-                    prev_command = command;
+            };
+            // a negative radius is an error and a zero one disables rendering
+            let names = if name == "circle" { ("r", "r") } else { ("rx", "ry") };
+            for (name, radius) in [(names.0, rx), (names.1, ry)] {
+                if radius < 0.0 {
+                    return Err(SvgError::attribute(name, &radius.to_string()));
                 }
-                "H" => {
-                    // Horizontal Line
-                    let mut end_point_x = i[1].parse::<f64>().unwrap() / x_scale;
-                    let mut end_point_y = prev_point.1;
-                    if let Some(ts) = transform_str {
-                        let transformed_points =
-                            transform(end_point_x, end_point_y, ts, (x_scale, y_scale));
-                        end_point_x = transformed_points.0;
-                        end_point_y = transformed_points.1;
-                    } else {
-                        continue;
-                    }
-                    points.push((end_point_x, end_point_y, true));
-                    prev_point = (end_point_x, end_point_y, true);
-                    prev_command = command;
+            }
+            if rx <= 0.0 || ry <= 0.0 {
+                return Ok(());
+            }
+            // two half arcs, flattened to the tolerance like any other path
+            let d = format!(
+                "M {} {cy} A {rx} {ry} 0 0 0 {} {cy} A {rx} {ry} 0 0 0 {} {cy} Z",
+                cx - rx,
+                cx + rx,
+                cx - rx,
+            );
+            add_shape(hash_map, draw_path(&d, ctm, flatness)?, state, true);
+        }
+        "polygon" | "polyline" => {
+            let points = attribute(attributes, "points").unwrap_or_default();
+            let numbers = parse_numbers(points).ok_or_else(|| SvgError::attribute("points", points))?;
+            // an odd trailing coordinate is ignored, as the spec asks
+            let pairs = numbers.chunks_exact(2).collect::<Vec<&[f64]>>();
+            if pairs.len() < 2 {
+                return Ok(());
+            }
+            let mut d = format!("M {} {}", pairs[0][0], pairs[0][1]);
+            for pair in &pairs[1..] {
+                d.push_str(&format!(" L {} {}", pair[0], pair[1]));
+            }
+            if name == "polygon" {
+                d.push_str(" Z");
+            }
+            add_shape(hash_map, draw_path(&d, ctm, flatness)?, state, true);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// the rules of every css `<style>` element in the document
+fn stylesheet(events: &[XmlEvent]) -> Stylesheet {
+    let mut stylesheet = Stylesheet::default();
+    // the text of the <style> element being read, if we are inside one
+    let mut css: Option<String> = None;
+    for event in events {
+        match event {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } if name.local_name == "style" => {
+                let css_type = attribute(attributes, "type").unwrap_or("text/css");
+                if css_type.trim().eq_ignore_ascii_case("text/css") {
+                    css = Some(String::new());
                 }
-                "V" => {
-                    // Vertical Line
-                    let mut end_point_x = prev_point.0;
-                    let mut end_point_y = 100.0 - i[1].parse::<f64>().unwrap() / y_scale;
-                    if let Some(ts) = transform_str {
-                        let transformed_points =
-                            transform(end_point_x, end_point_y, ts, (x_scale, y_scale));
-                        end_point_x = transformed_points.0;
-                        end_point_y = transformed_points.1;
-                    } else {
-                        continue;
-                    }
-                    //println!("{:?}", (end_point_x, end_point_y));
-                    points.push((end_point_x, end_point_y, true));
-                    prev_point = (end_point_x, end_point_y, true);
-                    prev_command = command;
+            }
+            XmlEvent::Characters(text) | XmlEvent::CData(text) | XmlEvent::Whitespace(text) => {
+                if let Some(css) = css.as_mut() {
+                    css.push_str(text);
+                }
+            }
+            XmlEvent::EndElement { name } if name.local_name == "style" => {
+                if let Some(css) = css.take() {
+                    stylesheet.parse(&css);
                 }
-
-                _ => {}
             }
+            _ => {}
         }
+    }
+    stylesheet
+}
 
-        //println!("{:?}", data);
+/// the state glyphs of `run` are stroked in: the pen is the colour the text is
+/// filled with, round and as wide as bold or regular strokes of the font size.
+/// `scale` is how much the user space of the run is scaled from that of the root
+fn glyph_state(run: &SvgText, scale: f64) -> GraphicsState {
+    let mut style = run.style.clone();
+    if style.fill_color().is_some() {
+        style.stroke = style.fill;
     }
-    // if "i" & "j" is within points than push to fill, i is 0 to 100,  j is 0 to 100
-    let x_points: Vec<f64> = points.iter().map(|x: &(f64, f64, bool)| x.0).collect();
-    let y_points: Vec<f64> = points.iter().map(|x: &(f64, f64, bool)| x.1).collect();
-    if FILL_RE.is_match(style) {
-        let x_min: usize = *x_points
-            .iter()
-            .min_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap() as usize
-            + 1;
-        let x_max: usize = *x_points
-            .iter()
-            .max_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap() as usize;
-        let y_min: usize = *y_points
-            .iter()
-            .min_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap() as usize
-            + 1;
-        let y_max: usize = *y_points
-            .iter()
-            .max_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap() as usize;
-        let mut fill: Vec<(f64, f64, bool)> = Vec::new();
-        for i in x_min..x_max {
-            for j in y_min..y_max {
-                fill.push((i as f64, j as f64, true));
+    style.fill = Paint::None;
+    style.stroke_width = style.font_size * if style.font_weight >= 600 { 0.14 } else { 0.08 };
+    style.stroke_linecap = LineCap::Round;
+    style.stroke_linejoin = LineJoin::Round;
+    style.stroke_dasharray = Vec::new();
+    GraphicsState {
+        style,
+        transform: Transform::scale(scale, scale),
+        clip: run.clip.clone(),
+        ..GraphicsState::default()
+    }
+}
+
+/// add a shape in paint order, its fill first so the stroke is drawn over it.
+/// fills and strokes keep the outline, they are only rasterised once the
+/// canvas resolution is known. stroke lengths are scaled to the user units of
+/// the root, which unlike canvas units are as long across the screen as down it
+fn add_shape(hash_map: &mut HashMap<usize, SvgPoints>, (points, closed): Path, state: &GraphicsState, fillable: bool) {
+    let style = state.style.scaled(state.transform.mean_scale());
+    if fillable && style.fill_color().is_some() {
+        hash_map.insert(hash_map.len(), (points.clone(), style.clone(), true, state.clip.clone(), closed.clone()));
+    }
+    hash_map.insert(hash_map.len(), (points, style, false, state.clip.clone(), closed));
+}
+
+/// flatten the path data in user space, then map every point through `ctm`,
+/// the transform from user space to canvas coordinates.
+/// curves stay within `tolerance` canvas units of the true curve
+fn draw_path(strings: &str, ctm: &Transform, tolerance: f64) -> Result<Path, SvgError> {
+    Ok(flatten_path(parse_path(strings)?, ctm, tolerance))
+}
+
+/// flatten parsed path data the way `draw_path` does
+fn flatten_path(segments: Vec<(char, Vec<f64>)>, ctm: &Transform, tolerance: f64) -> Path {
+    let mut points: Vec<(f64, f64, bool)> = Vec::new();
+    let mut closed = Vec::new();
+    // the same tolerance in user units, wherever the transform stretches the most
+    let tolerance = tolerance / ctm.max_scale();
+
+    let mut subpath_start = (0.0, 0.0);
+    let mut prev_point = (0.0, 0.0);
+    let mut prev_command = ' ';
+    let mut prev_control = (0.0, 0.0);
+    for (command, args) in absolute_commands(segments) {
+        // drawing on after a closepath starts a new subpath where the last one started
+        if prev_command == 'Z' && command != 'M' && command != 'Z' {
+            points.push((subpath_start.0, subpath_start.1, false));
+            closed.push(false);
+        }
+        match command {
+            'M' => {
+                // only the first moveto draws, later ones lift the pen
+                let pen = points.is_empty();
+                prev_point = (args[0], args[1]);
+                subpath_start = prev_point;
+                points.push((prev_point.0, prev_point.1, pen));
+                closed.push(false);
+            }
+            'L' => {
+                prev_point = (args[0], args[1]);
+                points.push((prev_point.0, prev_point.1, true));
+            }
+            'H' => {
+                // Horizontal Line
+                prev_point.0 = args[0];
+                points.push((prev_point.0, prev_point.1, true));
             }
+            'V' => {
+                // Vertical Line
+                prev_point.1 = args[0];
+                points.push((prev_point.0, prev_point.1, true));
+            }
+            'Q' | 'T' => {
+                // Quadratic Bezier Curve, T reflects the control point of the previous Q or T
+                let control = if command == 'Q' {
+                    (args[0], args[1])
+                } else if matches!(prev_command, 'Q' | 'T') {
+                    (
+                        2.0 * prev_point.0 - prev_control.0,
+                        2.0 * prev_point.1 - prev_control.1,
+                    )
+                } else {
+                    prev_point
+                };
+                let end = (args[args.len() - 2], args[args.len() - 1]);
+                // the same curve as a cubic, with its control points two thirds of the way to `control`
+                let control_1 = (
+                    prev_point.0 + 2.0 / 3.0 * (control.0 - prev_point.0),
+                    prev_point.1 + 2.0 / 3.0 * (control.1 - prev_point.1),
+                );
+                let control_2 = (
+                    end.0 + 2.0 / 3.0 * (control.0 - end.0),
+                    end.1 + 2.0 / 3.0 * (control.1 - end.1),
+                );
+                flatten_cubic([prev_point, control_1, control_2, end], tolerance, 16, &mut points);
+                prev_point = end;
+                prev_control = control;
+            }
+            'C' | 'S' => {
+                // Cubic Bezier Curve, S reflects the second control point of the previous C or S
+                let control_1 = if command == 'C' {
+                    (args[0], args[1])
+                } else if matches!(prev_command, 'C' | 'S') {
+                    (
+                        2.0 * prev_point.0 - prev_control.0,
+                        2.0 * prev_point.1 - prev_control.1,
+                    )
+                } else {
+                    prev_point
+                };
+                let control_2 = (args[args.len() - 4], args[args.len() - 3]);
+                let end = (args[args.len() - 2], args[args.len() - 1]);
+                flatten_cubic([prev_point, control_1, control_2, end], tolerance, 16, &mut points);
+                prev_point = end;
+                prev_control = control_2;
+            }
+            'A' => {
+                // Elliptical Arc, any nonzero flag counts as 1
+                let end = (args[5], args[6]);
+                let mut arc_points: Vec<(f64, f64, bool)> = elliptical_arc(
+                    prev_point,
+                    (args[0], args[1]),
+                    args[2],
+                    args[3] != 0.0,
+                    args[4] != 0.0,
+                    end,
+                    tolerance,
+                );
+                points.append(&mut arc_points);
+                prev_point = end;
+            }
+            'Z' => {
+                prev_point = subpath_start;
+                points.push((subpath_start.0, subpath_start.1, true));
+                if let Some(closed) = closed.last_mut() {
+                    *closed = true;
+                }
+            }
+            _ => {}
         }
+        prev_command = command;
+    }
+    let points = points
+        .into_iter()
+        .map(|(x, y, pen)| {
+            let (x, y) = ctm.apply(x, y);
+            (x, y, pen)
+        })
+        .collect();
+    (points, closed)
+}
 
-        (points, Some(fill))
-    } else {
-        (points, None)
-    }
-}
-
-fn quadratic_bezier_curve(
-    start: &(f64, f64, bool),
-    control: &(f64, f64),
-    end: &(f64, f64),
-    t: f64,
-    transform_str: Option<&str>,
-    scales: (f64, f64),
-) -> (f64, f64, bool) {
-    let x = (1.0 - t).powi(2) * start.0 + 2.0 * (1.0 - t) * t * control.0 + t.powi(2) * end.0;
-    let y = (1.0 - t).powi(2) * start.1 + 2.0 * (1.0 - t) * t * control.1 + t.powi(2) * end.1;
-    // if transform exists use transform, else return x,y
-    if let Some(ts) = transform_str {
-        let (x, y) = transform(x, y, ts, scales);
-        (x, y, true)
-    } else {
-        (x, y, true)
-    }
-}
-
-fn cubic_bezier_curve(
-    start: &(f64, f64, bool),
-    control_1: &(f64, f64),
-    control_2: &(f64, f64),
-    end: &(f64, f64),
-    t: f64,
-    transform_str: Option<&str>,
-    scales: (f64, f64),
-) -> (f64, f64, bool) {
-    let x = (1.0 - t).powi(3) * start.0
-        + 3.0 * (1.0 - t).powi(2) * t * control_1.0
-        + 3.0 * (1.0 - t) * t.powi(2) * control_2.0
-        + t.powi(3) * end.0;
-    let y = 100.0
-        - ((1.0 - t).powi(3) * start.1
-            + 3.0 * (1.0 - t).powi(2) * t * control_1.1
-            + 3.0 * (1.0 - t) * t.powi(2) * control_2.1
-            + t.powi(3) * end.1);
-    // if transform exists use transform, else return x,y
-    if let Some(ts) = transform_str {
-        let (x, y) = transform(x, y, ts, scales);
-        (x, y, true)
-    } else {
-        (x, y, true)
+/// rewrite every command as an absolute command.
+/// relative commands are offset by the current point, which is tracked in
+/// user units, and `z` moves the current point back to the subpath start
+fn absolute_commands(segments: Vec<(char, Vec<f64>)>) -> Vec<(char, Vec<f64>)> {
+    let mut commands = Vec::new();
+    let mut current = (0.0, 0.0);
+    let mut subpath_start = (0.0, 0.0);
+    for (command, mut args) in segments {
+        let relative = command.is_ascii_lowercase();
+        let command = command.to_ascii_uppercase();
+        if relative {
+            match command {
+                'H' => args[0] += current.0,
+                'V' => args[0] += current.1,
+                'A' => {
+                    args[5] += current.0;
+                    args[6] += current.1;
+                }
+                _ => {
+                    // every argument of m, l, c, s, q and t is an x/y pair
+                    for pair in args.chunks_exact_mut(2) {
+                        pair[0] += current.0;
+                        pair[1] += current.1;
+                    }
+                }
+            }
+        }
+        match command {
+            'M' => {
+                current = (args[0], args[1]);
+                subpath_start = current;
+            }
+            'Z' => current = subpath_start,
+            'H' => current.0 = args[0],
+            'V' => current.1 = args[0],
+            _ if args.len() >= 2 => current = (args[args.len() - 2], args[args.len() - 1]),
+            _ => {}
+        }
+        commands.push((command, args));
     }
+    commands
 }
 
+/// the point at `t` between `a` and `b`
+fn lerp(a: (f64, f64), b: (f64, f64), t: f64) -> (f64, f64) {
+    (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+}
+
+/// the distance from `point` to the line through `start` and `end`
+fn distance_to_line(point: (f64, f64), start: (f64, f64), end: (f64, f64)) -> f64 {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length = dx.hypot(dy);
+    if length == 0.0 {
+        return (point.0 - start.0).hypot(point.1 - start.1);
+    }
+    ((point.0 - start.0) * dy - (point.1 - start.1) * dx).abs() / length
+}
+
+/// push the points of a cubic bezier curve after its start point, halving it
+/// until each piece is within `tolerance` of a straight line or `depth` runs out.
+/// the last point is always exactly the end of the curve
+fn flatten_cubic(curve: [(f64, f64); 4], tolerance: f64, depth: u32, points: &mut Vec<(f64, f64, bool)>) {
+    let [start, control_1, control_2, end] = curve;
+    // the curve never strays more than 3/4 of its furthest control point from the chord
+    let deviation = distance_to_line(control_1, start, end).max(distance_to_line(control_2, start, end));
+    if depth == 0 || 0.75 * deviation <= tolerance {
+        points.push((end.0, end.1, true));
+        return;
+    }
+    // de Casteljau at t = 0.5
+    let ab = lerp(start, control_1, 0.5);
+    let bc = lerp(control_1, control_2, 0.5);
+    let cd = lerp(control_2, end, 0.5);
+    let abc = lerp(ab, bc, 0.5);
+    let bcd = lerp(bc, cd, 0.5);
+    let middle = lerp(abc, bcd, 0.5);
+    flatten_cubic([start, ab, abc, middle], tolerance, depth - 1, points);
+    flatten_cubic([middle, bcd, cd, end], tolerance, depth - 1, points);
+}
+
+/// the points of an arc after its start point, close enough together that
+/// the arc never strays more than `tolerance` from them.
+/// follows the endpoint to center conversion of SVG Implementation Notes F.6
 fn elliptical_arc(
-    start: (f64, f64, bool),
+    start: (f64, f64),
     radii: (f64, f64),
     x_axis_rotation: f64,
     large_arc_flag: bool,
     sweep_flag: bool,
     end: (f64, f64),
-    num_points: usize,
+    tolerance: f64,
 ) -> Vec<(f64, f64, bool)> {
-    let (mut rx, mut ry) = radii;
-    let (x1, y1) = (start.0, start.1);
+    // F.6.2, an arc between the same points is left out
+    if start == end {
+        return Vec::new();
+    }
+    // and an arc without a radius is a straight line
+    let (mut rx, mut ry) = (radii.0.abs(), radii.1.abs());
+    if rx == 0.0 || ry == 0.0 {
+        return vec![(end.0, end.1, true)];
+    }
+    let (x1, y1) = start;
     let (x2, y2) = end;
+    let (sin_phi, cos_phi) = x_axis_rotation.to_radians().sin_cos();
 
-    let angle_rad = x_axis_rotation.to_radians();
-    let cos_angle = angle_rad.cos();
-    let sin_angle = angle_rad.sin();
+    // F.6.5.1, the start point in a frame centred between the end points and
+    // lined up with the axes of the ellipse
+    let x1_prime = cos_phi * (x1 - x2) / 2.0 + sin_phi * (y1 - y2) / 2.0;
+    let y1_prime = -sin_phi * (x1 - x2) / 2.0 + cos_phi * (y1 - y2) / 2.0;
 
-    let x1_prime = cos_angle * (x1 - x2) / 2.0 + sin_angle * (y1 - y2) / 2.0;
-    let y1_prime = -sin_angle * (x1 - x2) / 2.0 + cos_angle * (y1 - y2) / 2.0;
-
-    let mut lambda = (x1_prime / rx).powi(2) + (y1_prime / ry).powi(2);
+    // F.6.6, radii too small to reach the end point grow until they just do
+    let lambda = (x1_prime / rx).powi(2) + (y1_prime / ry).powi(2);
     if lambda > 1.0 {
-        lambda = lambda.sqrt();
-        rx *= lambda;
-        ry *= lambda;
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
     }
 
-    let (cx_prime, cy_prime) = {
-        let sign = if large_arc_flag == sweep_flag {
-            -1.0
-        } else {
-            1.0
-        };
-        let factor = ((rx.powi(2) * ry.powi(2)
-            - rx.powi(2) * y1_prime.powi(2)
-            - ry.powi(2) * x1_prime.powi(2))
-            / (rx.powi(2) * y1_prime.powi(2) + ry.powi(2) * x1_prime.powi(2)))
-        .sqrt();
-        (
-            sign * factor * rx * y1_prime / ry,
-            -sign * factor * ry * x1_prime / rx,
-        )
-    };
+    // F.6.5.2, the centre in that frame. rounding can take the square root
+    // slightly below zero when the radii were just scaled up
+    let numerator = rx.powi(2) * ry.powi(2) - rx.powi(2) * y1_prime.powi(2) - ry.powi(2) * x1_prime.powi(2);
+    let denominator = rx.powi(2) * y1_prime.powi(2) + ry.powi(2) * x1_prime.powi(2);
+    let sign = if large_arc_flag != sweep_flag { 1.0 } else { -1.0 };
+    let coefficient = sign * (numerator / denominator).max(0.0).sqrt();
+    let cx_prime = coefficient * rx * y1_prime / ry;
+    let cy_prime = -coefficient * ry * x1_prime / rx;
 
-    let (cx, cy) = (
-        cos_angle * cx_prime - sin_angle * cy_prime + (x1 + x2) / 2.0,
-        sin_angle * cx_prime + cos_angle * cy_prime + (y1 + y2) / 2.0,
-    );
-
-    let start_angle = ((y1_prime - cy_prime) / ry).atan2((x1_prime - cx_prime) / rx);
-    let delta_angle = {
-        let delta_angle =
-            ((y1_prime * -1.0 - cy_prime) / ry).atan2((-x1_prime - cx_prime) / rx) - start_angle;
-        let delta_angle = if delta_angle * (sweep_flag as i32 as f64 * 2.0 - 1.0) < 0.0 {
-            delta_angle + 2.0 * std::f64::consts::PI
-        } else {
-            delta_angle
-        };
-        if sweep_flag {
-            delta_angle
-        } else {
-            -delta_angle
-        }
+    // F.6.5.3, the centre in user space
+    let cx = cos_phi * cx_prime - sin_phi * cy_prime + (x1 + x2) / 2.0;
+    let cy = sin_phi * cx_prime + cos_phi * cy_prime + (y1 + y2) / 2.0;
+
+    // F.6.5.5 and F.6.5.6, the start angle and how far the arc turns, positive
+    // angles turn towards positive y so a sweep flag of 1 turns that way
+    let u = ((x1_prime - cx_prime) / rx, (y1_prime - cy_prime) / ry);
+    let v = ((-x1_prime - cx_prime) / rx, (-y1_prime - cy_prime) / ry);
+    let start_angle = u.1.atan2(u.0);
+    let mut delta_angle = (u.0 * v.1 - u.1 * v.0).atan2(u.0 * v.0 + u.1 * v.1);
+    if !sweep_flag && delta_angle > 0.0 {
+        delta_angle -= 2.0 * std::f64::consts::PI;
+    } else if sweep_flag && delta_angle < 0.0 {
+        delta_angle += 2.0 * std::f64::consts::PI;
+    }
+
+    // a chord spanning `step` radians bulges radius * (1 - cos(step / 2)) from the arc
+    let radius = rx.max(ry);
+    let step = if tolerance < radius {
+        2.0 * (1.0 - tolerance / radius).acos()
+    } else {
+        std::f64::consts::PI
     };
+    let num_points = ((delta_angle.abs() / step).ceil() as usize).max(1);
 
+    // F.6.3, the parametric form of the rotated ellipse
     let mut points = Vec::with_capacity(num_points);
-    for i in 0..=num_points {
-        let t = i as f64 / num_points as f64;
-        let angle = start_angle + t * delta_angle;
-        let x = cx + rx * angle.cos();
-        let y = cy + ry * angle.sin();
-        let x_rotated = cos_angle * (x - cx) - sin_angle * (y - cy) + cx;
-        let y_rotated = sin_angle * (x - cx) + cos_angle * (y - cy) + cy;
-        points.push((x_rotated, y_rotated, true));
+    for i in 1..num_points {
+        let angle = start_angle + i as f64 / num_points as f64 * delta_angle;
+        let (sin, cos) = angle.sin_cos();
+        points.push((
+            cos_phi * rx * cos - sin_phi * ry * sin + cx,
+            sin_phi * rx * cos + cos_phi * ry * sin + cy,
+            true,
+        ));
     }
+    points.push((end.0, end.1, true));
     points
 }
-fn transform(x: f64, y: f64, transform: &str, scales: (f64, f64)) -> (f64, f64) {
-    if transform.starts_with("matrix") {
-        //matrix(0.963456, 0.267867, -0.267867, 0.963456, -67.327988, 51.384823)
-        let data = transform.strip_prefix("matrix(").unwrap();
-        let data = data.strip_suffix(')').unwrap();
-        let data = data.split(", ").collect::<Vec<&str>>();
-        let a = data[0].parse::<f64>().unwrap();
-        let b = data[1].parse::<f64>().unwrap();
-        let c = data[2].parse::<f64>().unwrap();
-        let d = data[3].parse::<f64>().unwrap();
-        let e = data[4].parse::<f64>().unwrap();
-        let f = data[5].parse::<f64>().unwrap();
-        let x = x * a + y * c + e;
-        let y = x * b + y * d + f;
-        (x, y)
-    } else if transform.starts_with("translate") {
-        let data = transform.strip_prefix("translate(").unwrap();
-        let data = data.strip_suffix(')').unwrap();
-        let data = data.split(", ").collect::<Vec<&str>>();
-        let x = x + data[0].parse::<f64>().unwrap() / scales.0;
-        let y = y + data[1].parse::<f64>().unwrap() / scales.1;
-        return (x, y);
-    } else if transform.starts_with("scale") {
-        let data = transform.strip_prefix("scale(").unwrap();
-        let data = data.strip_suffix(')').unwrap();
-        let data = data.split(", ").collect::<Vec<&str>>();
-        let x = x * data[0].parse::<f64>().unwrap();
-        let y = y * data[1].parse::<f64>().unwrap();
-        return (x, y);
-    } else if transform.starts_with("rotate") {
-        let data = transform.strip_prefix("rotate(").unwrap();
-        let data = data.strip_suffix(')').unwrap();
-        let data = data.split(' ').collect::<Vec<&str>>();
-        let angle = data[0].parse::<f64>().unwrap();
-        let angle_rad = angle.to_radians();
-        let cos_a = angle_rad.cos();
-        let sin_a = angle_rad.sin();
-        let new_x = x * cos_a - y * sin_a;
-        let new_y = x * sin_a + y * cos_a;
-
-        return (new_x, new_y);
-    } else if transform.starts_with("skewX") {
-        let data = transform.strip_prefix("skewX(").unwrap();
-        let data = data.strip_suffix(')').unwrap();
-        let data = data.split(' ').collect::<Vec<&str>>();
-        let angle = data[0].parse::<f64>().unwrap();
-        let x = x + y * angle.tan();
-        return (x, y);
-    } else if transform.starts_with("skewY") {
-        let data = transform.strip_prefix("skewY(").unwrap();
-        let data = data.strip_suffix(')').unwrap();
-        let data = data.split(' ').collect::<Vec<&str>>();
-        let angle = data[0].parse::<f64>().unwrap();
-        let y = y + x * angle.tan();
-        return (x, y);
-    } else {
-        return (x, y);
+
+/// parse a numeric attribute value
+fn number(name: &str, value: &str) -> Result<f64, SvgError> {
+    value
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| SvgError::attribute(name, value))
+}
+
+/// parse an optional length attribute, where a percentage is of `reference`
+fn length(name: &str, value: Option<&str>, reference: f64) -> Result<Option<f64>, SvgError> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    match value.trim().strip_suffix('%') {
+        Some(percentage) => Ok(Some(number(name, percentage)? / 100.0 * reference)),
+        None => absolute_length(name, value).map(Some),
+    }
+}
+
+/// a length in user units, which are CSS pixels, converting the absolute CSS
+/// units at 96 pixels to the inch
+fn absolute_length(name: &str, value: &str) -> Result<f64, SvgError> {
+    let value = value.trim();
+    let units = [("px", 1.0), ("in", 96.0), ("cm", 96.0 / 2.54), ("mm", 96.0 / 25.4), ("pt", 96.0 / 72.0), ("pc", 16.0)];
+    let (number, per_unit) = units
+        .iter()
+        .find_map(|(unit, per_unit)| Some((value.strip_suffix(unit)?, *per_unit)))
+        .unwrap_or((value, 1.0));
+    number
+        .parse::<f64>()
+        .map(|length| length * per_unit)
+        .map_err(|_| SvgError::attribute(name, value))
+}
+
+/// the id a `href` or older `xlink:href` refers to within this document.
+/// the plain `href` wins when both are given
+fn href(attributes: &[OwnedAttribute]) -> Option<&str> {
+    attributes
+        .iter()
+        .filter(|i| i.name.local_name == "href")
+        .min_by_key(|i| i.name.prefix.is_some())
+        .and_then(|i| i.value.trim().strip_prefix('#'))
+}
+
+/// the value of the attribute with this local name
+fn attribute<'a>(attributes: &'a [OwnedAttribute], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|i| i.name.local_name == name)
+        .map(|i| i.value.as_str())
+}
+
+/// splits a number list such as the `points` attribute, where commas and
+/// whitespace are optional wherever the numbers stay unambiguous.
+/// `None` if anything else is in the list
+fn parse_numbers(list: &str) -> Option<Vec<f64>> {
+    let bytes = list.as_bytes();
+    let mut numbers = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i].is_ascii_whitespace() || bytes[i] == b',' {
+            i += 1;
+            continue;
+        }
+        let (number, next) = scan_number(bytes, i)?;
+        numbers.push(number);
+        i = next;
+    }
+    Some(numbers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+
+    fn outline(d: &str) -> Vec<(f64, f64, bool)> {
+        draw_path(d, &Transform::identity(), 0.1).unwrap().0
+    }
+
+    fn render(svg: &str) -> HashMap<usize, SvgPoints> {
+        render_with_text(svg).0
+    }
+
+    fn render_with_text(svg: &str) -> (HashMap<usize, SvgPoints>, Vec<SvgText>) {
+        render_with_options(svg, &RenderOptions::default())
+    }
+
+    /// render into 50 by 25 cells, so the 100 by 100 canvas has cells 2 wide and 4 tall
+    fn render_with_options(svg: &str, options: &RenderOptions) -> (HashMap<usize, SvgPoints>, Vec<SvgText>) {
+        let (mut hash_map, mut texts) = (HashMap::new(), Vec::new());
+        let layout = Rect::new(0, 0, 50, 25);
+        render_svg(svg.to_string(), &mut hash_map, &mut texts, layout, options).unwrap();
+        (hash_map, texts)
+    }
+
+    /// the errors of the elements left out of the document
+    fn errors(svg: &str) -> Vec<SvgError> {
+        let (mut hash_map, mut texts) = (HashMap::new(), Vec::new());
+        let layout = Rect::new(0, 0, 50, 25);
+        render_document(svg.to_string(), &mut hash_map, &mut texts, layout, &RenderOptions::default()).unwrap().2
+    }
+
+    /// the attributes of the first element of `svg`
+    fn root_attributes(svg: &str) -> Vec<OwnedAttribute> {
+        let events = xml::reader::EventReader::from_str(svg).into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+        let Some(XmlEvent::StartElement { attributes, .. }) = events.into_iter().nth(1) else {
+            panic!("no root in {svg}");
+        };
+        attributes
+    }
+
+    fn traced(svg: &str) -> Vec<SvgPoints> {
+        let options = RenderOptions {
+            text_mode: TextMode::Strokes,
+            ..RenderOptions::default()
+        };
+        let (hash_map, texts) = render_with_options(svg, &options);
+        assert!(texts.is_empty());
+        let mut shapes = hash_map.into_iter().collect::<Vec<_>>();
+        shapes.sort_by_key(|(key, _)| *key);
+        shapes.into_iter().map(|(_, points)| points).collect()
+    }
+
+    fn assert_same_outline(absolute: &str, relative: &str) {
+        let absolute_points = outline(absolute);
+        let relative_points = outline(relative);
+        assert_eq!(absolute_points.len(), relative_points.len());
+        for (a, r) in absolute_points.iter().zip(&relative_points) {
+            assert!(
+                (a.0 - r.0).abs() < 1e-9 && (a.1 - r.1).abs() < 1e-9 && a.2 == r.2,
+                "{a:?} != {r:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn relative_lines() {
+        assert_same_outline(
+            "M 10 10 L 30 10 L 30 40 L 10 40 Z",
+            "m 10 10 l 20 0 l 0 30 l -20 0 z",
+        );
+    }
+
+    #[test]
+    fn relative_horizontal_vertical() {
+        assert_same_outline("M 10 10 H 60 V 70 H 10 Z", "m 10 10 h 50 v 60 h -50 z");
+    }
+
+    #[test]
+    fn relative_cubic() {
+        assert_same_outline(
+            "M 10 50 C 20 20 40 20 50 50 S 80 80 90 50",
+            "m 10 50 c 10 -30 30 -30 40 0 s 30 30 40 0",
+        );
+    }
+
+    #[test]
+    fn relative_quadratic() {
+        assert_same_outline(
+            "M 10 50 Q 30 10 50 50 T 90 50",
+            "m 10 50 q 20 -40 40 0 t 40 0",
+        );
+    }
+
+    #[test]
+    fn relative_arc() {
+        assert_same_outline(
+            "M 20 50 A 30 25 30 0 1 60 50",
+            "m 20 50 a 30 25 30 0 1 40 0",
+        );
+    }
+
+    /// the arc of `d` stays on the axis aligned ellipse around `center` and passes `through`
+    fn assert_arc(d: &str, center: (f64, f64), radii: (f64, f64), through: (f64, f64)) {
+        // finely enough that some point lands near `through`
+        let points = draw_path(d, &Transform::identity(), 0.001).unwrap().0;
+        for point in &points {
+            let distance = ((point.0 - center.0) / radii.0).hypot((point.1 - center.1) / radii.1);
+            assert!((distance - 1.0).abs() < 1e-6, "{d}: {point:?} is off the ellipse");
+        }
+        let closest = points
+            .iter()
+            .map(|point| (point.0 - through.0).hypot(point.1 - through.1))
+            .fold(f64::INFINITY, f64::min);
+        assert!(closest < 1.0, "{d} misses {through:?}");
+    }
+
+    #[test]
+    fn arc_flags() {
+        // the two circles of radius 50 through (0, 0) and (50, 50) are centred
+        // on (0, 50) and (50, 0), y points down so a sweep of 1 turns clockwise
+        let offset = 50.0 * std::f64::consts::FRAC_1_SQRT_2;
+        assert_arc("M 0 0 A 50 50 0 0 0 50 50", (50.0, 0.0), (50.0, 50.0), (50.0 - offset, offset));
+        assert_arc("M 0 0 A 50 50 0 0 1 50 50", (0.0, 50.0), (50.0, 50.0), (offset, 50.0 - offset));
+        assert_arc("M 0 0 A 50 50 0 1 0 50 50", (0.0, 50.0), (50.0, 50.0), (-offset, 50.0 + offset));
+        assert_arc("M 0 0 A 50 50 0 1 1 50 50", (50.0, 0.0), (50.0, 50.0), (50.0 + offset, -offset));
+        // every combination ends exactly at the end point
+        for flags in ["0 0", "0 1", "1 0", "1 1"] {
+            let points = outline(&format!("M 0 0 A 50 50 0 {flags} 50 50"));
+            assert_eq!(points.last(), Some(&(50.0, 50.0, true)));
+        }
+    }
+
+    #[test]
+    fn arc_radii_out_of_range() {
+        // too small radii scale up to a half circle
+        assert_arc("M 0 0 A 1 1 0 0 1 100 0", (50.0, 0.0), (50.0, 50.0), (50.0, -50.0));
+        assert_arc("M 0 0 A -1 1 0 0 0 100 0", (50.0, 0.0), (50.0, 50.0), (50.0, 50.0));
+        // a rotated ellipse keeps its shape as it scales
+        assert_arc("M 0 0 A 4 2 90 0 1 0 100", (0.0, 50.0), (25.0, 50.0), (25.0, 50.0));
+        // no radius is a straight line, and no distance is nothing at all
+        assert_eq!(outline("M 0 0 A 0 10 0 0 1 100 0"), vec![(0.0, 0.0, true), (100.0, 0.0, true)]);
+        assert_eq!(outline("M 0 0 A 10 10 0 0 1 0 0"), vec![(0.0, 0.0, true)]);
+    }
+
+    #[test]
+    fn relative_after_close_path() {
+        // z moves the current point back to the start of the subpath
+        assert_same_outline(
+            "M 10 10 L 40 10 L 40 40 Z M 20 20 L 60 60",
+            "m 10 10 l 30 0 l 0 30 z m 10 10 l 40 40",
+        );
+        assert_same_outline(
+            "M 10 10 L 40 10 L 40 40 Z L 60 60",
+            "m 10 10 l 30 0 l 0 30 z l 50 50",
+        );
+    }
+
+    #[test]
+    fn closepath_marks_its_subpath_closed() {
+        let closed = |d| draw_path(d, &Transform::identity(), 0.1).unwrap().1;
+        // coming back to the start is not closing the subpath
+        assert_eq!(closed("M 0 0 L 10 0 L 10 10 L 0 0"), vec![false]);
+        assert_eq!(closed("M 0 0 L 10 0 L 10 10 Z M 5 5 L 6 6"), vec![true, false]);
+        assert_eq!(closed("M 0 0 L 10 0 Z Z"), vec![true]);
+        // drawing on after z starts a new subpath at the start of the closed one
+        let (points, closed) = draw_path("M 0 0 L 10 0 L 10 10 Z L 5 5", &Transform::identity(), 0.1).unwrap();
+        assert_eq!(closed, vec![true, false]);
+        assert_eq!(points[4..], [(0.0, 0.0, false), (5.0, 5.0, true)]);
+    }
+
+    #[test]
+    fn curves_flatten_to_the_tolerance() {
+        for d in ["M 0 0 Q 50 100 100 0", "M 0 0 C 0 100 100 100 100 0", "M 0 0 A 50 50 0 0 1 100 0"] {
+            let coarse = draw_path(d, &Transform::identity(), 1.0).unwrap().0;
+            let fine = draw_path(d, &Transform::identity(), 0.01).unwrap().0;
+            assert!(coarse.len() < fine.len(), "{d}");
+            // the end point is exact, not the last sample before it
+            assert_eq!(coarse.last(), Some(&(100.0, 0.0, true)), "{d}");
+            assert_eq!(fine.last(), Some(&(100.0, 0.0, true)), "{d}");
+            // a stretched path needs more points for the same tolerance
+            let scaled = draw_path(d, &Transform::scale(10.0, 10.0), 1.0).unwrap().0;
+            assert!(scaled.len() > coarse.len(), "{d}");
+        }
+        // every point of the quadratic is within the tolerance of the flattened line
+        let flattened = draw_path("M 0 0 Q 50 100 100 0", &Transform::identity(), 0.5).unwrap().0;
+        for i in 0..=1000 {
+            let t = i as f64 / 1000.0;
+            let point = (100.0 * t, 200.0 * t * (1.0 - t));
+            let distance = flattened
+                .windows(2)
+                .map(|segment| {
+                    let (a, b) = ((segment[0].0, segment[0].1), (segment[1].0, segment[1].1));
+                    let along = ((point.0 - a.0) * (b.0 - a.0) + (point.1 - a.1) * (b.1 - a.1))
+                        / ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2));
+                    let closest = lerp(a, b, along.clamp(0.0, 1.0));
+                    (point.0 - closest.0).hypot(point.1 - closest.1)
+                })
+                .fold(f64::INFINITY, f64::min);
+            assert!(distance <= 0.5, "{point:?} is {distance} away");
+        }
+    }
+
+    #[test]
+    fn groups_pass_state_to_children() {
+        let hash_map = render(
+            r#"<svg viewBox="0 0 100 100">
+                <g transform="translate(10 0)" style="stroke: rgb(255, 0, 0); fill: none;">
+                    <g transform="scale(2)" stroke="blue">
+                        <line x1="0" y1="0" x2="10" y2="0" style="stroke: rgb(0, 255, 0);"/>
+                    </g>
+                    <line x1="0" y1="0" x2="10" y2="0"/>
+                </g>
+                <line x1="0" y1="0" x2="10" y2="0"/>
+            </svg>"#,
+        );
+        let mut lines = hash_map.into_values().collect::<Vec<SvgPoints>>();
+        lines.sort_by(|a, b| a.0[1].0.total_cmp(&b.0[1].0));
+        // the nested line is scaled and then moved by both groups
+        assert_eq!(lines[2].0[1].0, 30.0);
+        assert_eq!(lines[2].1.stroke_color(), Some(Color::Rgb(0, 255, 0)));
+        assert_eq!(lines[2].1.fill_color(), None);
+        assert_eq!(lines[1].0[1].0, 20.0);
+        assert_eq!(lines[1].1.stroke_color(), Some(Color::Rgb(255, 0, 0)));
+        assert_eq!(lines[1].1.fill_color(), None);
+        // nothing leaks out of the group once it is closed
+        assert_eq!(lines[0].0[1].0, 10.0);
+        assert_eq!(lines[0].1, Style::default());
+    }
+
+    #[test]
+    fn use_copies_what_it_refers_to() {
+        let hash_map = render(
+            r##"<svg viewBox="0 0 100 100" xmlns:xlink="http://www.w3.org/1999/xlink">
+                <style>#dot { stroke: blue }</style>
+                <defs><line id="dot" x1="0" y1="0" x2="10" y2="0" stroke="red"/></defs>
+                <use href="#dot" x="20" y="5" stroke="lime"/>
+                <g stroke="lime"><use xlink:href="#dot" x="50"/></g>
+                <use href="#missing"/>
+            </svg>"##,
+        );
+        let mut lines = hash_map.into_values().collect::<Vec<SvgPoints>>();
+        lines.sort_by(|a, b| a.0[0].0.total_cmp(&b.0[0].0));
+        // the definition itself is not drawn
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].0, vec![(20.0, 95.0, true), (30.0, 95.0, true)]);
+        assert_eq!(lines[1].0[0], (50.0, 100.0, true));
+        // stylesheets still match the original, over what it inherits from the <use>
+        assert_eq!(lines[0].1.stroke_color(), Some(Color::Rgb(0, 0, 255)));
+    }
+
+    #[test]
+    fn symbols_fit_their_view_box_into_the_use() {
+        let hash_map = render(
+            r##"<svg viewBox="0 0 100 100">
+                <symbol id="icon" viewBox="0 0 10 10" width="50" height="50">
+                    <line x1="0" y1="0" x2="10" y2="10" stroke="red"/>
+                </symbol>
+                <use href="#icon"/>
+                <use href="#icon" x="50" y="50" width="20" height="40"/>
+            </svg>"##,
+        );
+        let mut lines = hash_map.into_values().collect::<Vec<SvgPoints>>();
+        lines.sort_by(|a, b| a.0[0].0.total_cmp(&b.0[0].0));
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].0, vec![(0.0, 100.0, true), (50.0, 50.0, true)]);
+        // meet keeps the icon square and centres it in the taller viewport
+        assert_eq!(lines[1].0, vec![(50.0, 40.0, true), (70.0, 20.0, true)]);
+    }
+
+    #[test]
+    fn nested_viewports() {
+        let hash_map = render(
+            r#"<svg viewBox="0 0 100 100">
+                <svg x="50" y="0" width="50%" height="20" viewBox="0 0 10 10">
+                    <line x1="0" y1="0" x2="10" y2="10" stroke="red"/>
+                </svg>
+                <svg x="10" y="60" width="20" height="20" overflow="visible">
+                    <line x1="0" y1="0" x2="10" y2="0" stroke="red"/>
+                </svg>
+                <line x1="0" y1="0" x2="10" y2="0" stroke="red"/>
+            </svg>"#,
+        );
+        let mut lines = hash_map.into_iter().collect::<Vec<(usize, SvgPoints)>>();
+        lines.sort_by_key(|(key, _)| *key);
+        let lines = lines.into_iter().map(|(_, line)| line).collect::<Vec<SvgPoints>>();
+        // a 10 by 10 view box meets a 50 by 20 viewport at 2 units each, centred across it
+        assert_eq!(lines[0].0, vec![(65.0, 100.0, true), (85.0, 80.0, true)]);
+        assert_eq!(lines[0].3, vec![vec![(50.0, 100.0), (100.0, 100.0), (100.0, 80.0), (50.0, 80.0)]]);
+        // no view box only moves the origin, and a visible overflow is not clipped
+        assert_eq!(lines[1].0, vec![(10.0, 40.0, true), (20.0, 40.0, true)]);
+        assert!(lines[1].3.is_empty());
+        // nothing leaks out to the elements after a nested viewport
+        assert_eq!(lines[2].0, vec![(0.0, 100.0, true), (10.0, 100.0, true)]);
+    }
+
+    #[test]
+    fn text_runs() {
+        let (_, texts) = render_with_text(
+            r#"<svg viewBox="0 0 100 100">
+                <text x="10" y="20" fill="red">
+                    Hello,
+                    <tspan dx="5" font-weight="bold">big</tspan>
+                    <tspan x="30" dy="-10">world </tspan>
+                </text>
+                <g transform="scale(2)"><text dy="5">x</text></g>
+            </svg>"#,
+        );
+        let runs = texts.iter().map(|text| text.text.as_str()).collect::<Vec<&str>>();
+        assert_eq!(runs, vec!["Hello, ", "big", " ", "world", "", "x"]);
+        assert_eq!(texts[0].position, Some((10.0, 80.0)));
+        assert_eq!(texts[1].position, None);
+        assert_eq!(texts[1].shift, (5.0, 0.0));
+        assert_eq!(texts[1].style.font_weight, 700);
+        assert_eq!(texts[1].style.fill_color(), Some(Color::Rgb(255, 0, 0)));
+        // an x alone keeps the last y, and the shift is scaled but not moved by the transform
+        assert_eq!(texts[3].position, Some((30.0, 80.0)));
+        assert_eq!(texts[3].shift, (0.0, 10.0));
+        assert_eq!(texts[5].position, Some((0.0, 100.0)));
+        assert_eq!(texts[5].shift, (0.0, -10.0));
+    }
+
+    #[test]
+    fn stroke_text_traces_glyphs() {
+        // one stroke per glyph, 24 units wide in all and centred on x = 50
+        let glyphs = traced(
+            r#"<svg viewBox="0 0 100 100"><text x="50" y="50" font-size="20" text-anchor="middle" fill="red">HI</text></svg>"#,
+        );
+        assert_eq!(glyphs.len(), 2);
+        for (points, style, filled, ..) in &glyphs {
+            assert!(!filled);
+            assert_eq!(style.stroke_color(), Some(Color::Rgb(255, 0, 0)));
+            assert_eq!(style.stroke_width, 1.6);
+            assert!(points.iter().all(|p| (38.0..=62.0).contains(&p.0) && (50.0..=64.0).contains(&p.1)));
+        }
+        // a rotated run goes down the canvas with the tops of its capitals to the right
+        let glyphs = traced(
+            r#"<svg viewBox="0 0 100 100"><text x="50" y="50" font-size="10" transform="rotate(90 50 50)">HI</text></svg>"#,
+        );
+        let points = glyphs.iter().flat_map(|glyph| glyph.0.iter()).collect::<Vec<_>>();
+        assert!(points.iter().all(|p| (50.0..=57.0).contains(&p.0) && (38.0..=50.0).contains(&p.1)));
+    }
+
+    #[test]
+    fn text_follows_a_path() {
+        let (_, texts) = render_with_text(
+            r##"<svg viewBox="0 0 100 100">
+                <defs><path id="across" d="M 10 50 H 90"/><path id="down" d="M 50 10 V 90"/></defs>
+                <text><textPath href="#across" startOffset="10">abc</textPath></text>
+                <text text-anchor="end"><textPath href="#across" startOffset="100%">xyz</textPath></text>
+                <text><textPath href="#down">de</textPath></text>
+                <text><textPath href="#missing">gone</textPath></text>
+            </svg>"##,
+        );
+        let placed = texts
+            .iter()
+            .map(|text| (text.text.as_str(), text.position.unwrap()))
+            .collect::<Vec<_>>();
+        // one cell apart along the path, which is 2 across and 4 down
+        assert_eq!(
+            placed,
+            vec![
+                ("a", (20.0, 50.0)),
+                ("b", (22.0, 50.0)),
+                ("c", (24.0, 50.0)),
+                ("x", (84.0, 50.0)),
+                ("y", (86.0, 50.0)),
+                ("z", (88.0, 50.0)),
+                ("d", (50.0, 90.0)),
+                ("e", (50.0, 86.0)),
+            ]
+        );
+        assert!(texts.iter().all(|text| text.style.text_anchor == TextAnchor::Start));
+        // characters past the end of the path are dropped
+        let (_, texts) = render_with_text(
+            r##"<svg viewBox="0 0 100 100"><path id="p" d="M 0 0 H 4.5"/><text><textPath href="#p">abcd</textPath></text></svg>"##,
+        );
+        assert_eq!(texts.iter().map(|text| text.text.as_str()).collect::<String>(), "ab");
+    }
+
+    #[test]
+    fn traced_text_turns_with_its_path() {
+        // going down the page the tops of the glyphs face right
+        let glyphs = traced(
+            r##"<svg viewBox="0 0 100 100"><defs><path id="down" d="M 50 10 V 90"/></defs><text font-size="10"><textPath href="#down">HI</textPath></text></svg>"##,
+        );
+        assert_eq!(glyphs.len(), 2);
+        for (points, ..) in &glyphs {
+            assert!(points.iter().all(|p| (50.0..=57.0).contains(&p.0) && (78.0..=90.0).contains(&p.1)));
+        }
+    }
+
+    #[test]
+    fn documents_are_told_from_snippets_by_their_root() {
+        assert!(is_document(r#"<?xml version="1.0"?><!-- drawn --><svg viewBox="0 0 10 10"><rect/></svg>"#));
+        assert!(!is_document(r#"<rect width="5"/><svg x="5"><rect/></svg>"#));
+        assert!(!is_document(r#"<g><svg><rect/></svg></g>"#));
+        assert!(!is_document(""));
+    }
+
+    #[test]
+    fn embedded_content_is_skipped() {
+        let hash_map = render(
+            r#"<svg viewBox="0 0 100 100">
+                <image href="photo.png" width="50" height="50"/>
+                <foreignObject width="50" height="50"><rect width="10" height="10" stroke="red"/></foreignObject>
+                <line x1="0" y1="0" x2="10" y2="0" stroke="red"/>
+            </svg>"#,
+        );
+        assert_eq!(hash_map.len(), 1);
+        let errors = errors(r#"<svg viewBox="0 0 100 100"><image href="photo.png"/></svg>"#);
+        assert!(matches!(&errors[..], [SvgError::Unsupported(name)] if name == "image"));
+    }
+
+    #[test]
+    fn elements_in_error_are_left_out() {
+        let svg = r#"<svg viewBox="0 0 100 100">
+            <path d="M 0 0 L 10 10 L 20" stroke="red" fill="none"/>
+            <g transform="rotate(10deg)"><rect width="50" height="50" stroke="red"/></g>
+            <rect x="50" width="10" height="10" stroke="blue" fill="none"/>
+        </svg>"#;
+        let mut shapes = render(svg).into_values().collect::<Vec<SvgPoints>>();
+        shapes.sort_by_key(|shape| shape.0.len());
+        // the path is drawn up to its error, the group is left out and the rect still drawn
+        assert_eq!(shapes.len(), 2);
+        let path = shapes[0].0.iter().map(|point| (point.0, point.1)).collect::<Vec<_>>();
+        assert_eq!(path, vec![(0.0, 100.0), (10.0, 90.0)]);
+        assert_eq!(shapes[1].1.stroke_color(), Some(Color::Rgb(0, 0, 255)));
+        let errors = errors(svg);
+        assert!(matches!(&errors[..], [SvgError::PathData(_), SvgError::Attribute { name, .. }] if name == "transform"));
+        // only an error in the root fails the whole document
+        let (mut hash_map, mut texts) = (HashMap::new(), Vec::new());
+        let root = r#"<svg viewBox="0 0 -1 1"/>"#.to_string();
+        assert!(render_svg(root, &mut hash_map, &mut texts, Rect::new(0, 0, 50, 25), &RenderOptions::default()).is_err());
+    }
+
+    #[test]
+    fn resources_are_not_drawn_in_place() {
+        let hash_map = render(
+            r#"<svg viewBox="0 0 100 100">
+                <clipPath id="c"><rect width="100" height="50"/></clipPath>
+                <mask id="m"><rect width="100" height="50" fill="white"/></mask>
+                <marker id="arrow"><path d="M 0 0 L 10 5 L 0 10 Z"/></marker>
+                <pattern id="p" width="10" height="10"><circle cx="5" cy="5" r="2"/></pattern>
+                <linearGradient id="l"><stop offset="0"/></linearGradient>
+                <radialGradient id="r"><stop offset="1"/></radialGradient>
+            </svg>"#,
+        );
+        assert!(hash_map.is_empty());
+    }
+
+    #[test]
+    fn view_box_attributes() {
+        let view_box = |svg: &str| {
+            ViewBox::from_attributes(&root_attributes(svg)).map(|view_box| (view_box.min_x, view_box.min_y, view_box.width, view_box.height)).ok()
+        };
+        // found by name wherever it is among the other attributes
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="300" height="150" viewBox="0 0 30 15"/>"#;
+        assert_eq!(view_box(svg), Some((0.0, 0.0, 30.0, 15.0)));
+        assert_eq!(view_box(r#"<svg viewBox="-10,5,20,40"/>"#), Some((-10.0, 5.0, 20.0, 40.0)));
+        assert_eq!(view_box("<svg viewBox=\" 1,\t2\n3 , 4 \"/>"), Some((1.0, 2.0, 3.0, 4.0)));
+        for invalid in ["0 0 10", "0 0 10 10 10", "0 0 -10 10", "0 0 10 0", "0 0 10px 10", ""] {
+            assert_eq!(view_box(&format!(r#"<svg viewBox="{invalid}"/>"#)), None, "{invalid:?}");
+        }
+        // without a view box the width and height are the user space, from the origin
+        assert_eq!(view_box(r#"<svg width="300" height="150"/>"#), Some((0.0, 0.0, 300.0, 150.0)));
+        assert_eq!(view_box(r#"<svg width="300"/>"#), Some((0.0, 0.0, 300.0, 100.0)));
+        assert_eq!(view_box(r#"<svg width="50%" height="150"/>"#), Some((0.0, 0.0, 100.0, 150.0)));
+        assert_eq!(view_box("<svg/>"), Some((0.0, 0.0, 100.0, 100.0)));
+
+        // the corners of a view box away from the origin are the corners of the canvas
+        let matrix = ViewBox::from_attributes(&root_attributes(r#"<svg viewBox="50 -20 200 40"/>"#)).unwrap().matrix();
+        assert_eq!(matrix.apply(50.0, -20.0), (0.0, 100.0));
+        assert_eq!(matrix.apply(250.0, 20.0), (100.0, 0.0));
+        assert_eq!(matrix.apply(150.0, 0.0), (50.0, 50.0));
+    }
+
+    #[test]
+    fn sizes_in_absolute_units() {
+        let size = |svg: &str| ViewBox::from_attributes(&root_attributes(svg)).map(|view_box| (view_box.width, view_box.height));
+        let (width, height) = size(r#"<svg width="210mm" height="12pt"/>"#).unwrap();
+        assert!((width - 793.7).abs() < 0.1, "{width}");
+        assert!((height - 16.0).abs() < 1e-9, "{height}");
+        assert_eq!(size(r#"<svg width="1in" height="2pc"/>"#).unwrap(), (96.0, 32.0));
+        assert_eq!(size(r#"<svg width="2.54cm" height="10px"/>"#).unwrap(), (96.0, 10.0));
+        assert!(size(r#"<svg width="10furlongs"/>"#).is_err());
+    }
+
+    #[test]
+    fn aspect_ratio_attributes() {
+        use Align::{Max, Mid, Min};
+        let parse = |value: &str| {
+            let svg = format!(r#"<svg preserveAspectRatio="{value}"/>"#);
+            PreserveAspectRatio::from_attributes(&root_attributes(&svg)).map(|ratio| (ratio.align, ratio.slice)).ok()
+        };
+        let table = [
+            ("xMidYMid", Some((Some((Mid, Mid)), false))),
+            ("xMinYMax slice", Some((Some((Min, Max)), true))),
+            ("xMaxYMin meet", Some((Some((Max, Min)), false))),
+            ("  xMidYMin \t slice ", Some((Some((Mid, Min)), true))),
+            ("defer xMaxYMid", Some((Some((Max, Mid)), false))),
+            ("none", Some((None, false))),
+            ("none slice", Some((None, true))),
+            ("", None),
+            ("slice", None),
+            ("xminymin", None),
+            ("xMidYMad", None),
+            ("yMidxMid", None),
+            ("xMidYMid stretch", None),
+            ("xMidYMid meet slice", None),
+            ("xMidYMid defer", None),
+        ];
+        for (value, expected) in table {
+            assert_eq!(parse(value), expected, "{value:?}");
+        }
+        let missing = PreserveAspectRatio::from_attributes(&root_attributes("<svg/>")).unwrap();
+        assert_eq!(missing, PreserveAspectRatio::default());
+    }
+
+    #[test]
+    fn empty_ellipses_draw_nothing() {
+        let hash_map = render(
+            r#"<svg viewBox="0 0 100 100">
+                <circle cx="50" cy="50" r="0" stroke="red"/>
+                <circle cx="50" cy="50" stroke="red"/>
+                <ellipse cx="50" cy="50" rx="10" ry="0" stroke="red"/>
+            </svg>"#,
+        );
+        assert!(hash_map.is_empty());
+        let errors = errors(r#"<svg viewBox="0 0 100 100"><circle r="-1"/></svg>"#);
+        assert!(matches!(&errors[..], [SvgError::Attribute { name, .. }] if name == "r"));
+    }
+
+    #[test]
+    fn empty_rects_draw_nothing() {
+        let hash_map = render(
+            r#"<svg viewBox="0 0 100 100">
+                <rect width="10" height="0" stroke="red"/>
+                <rect width="0" height="10" stroke="red"/>
+                <rect stroke="red"/>
+            </svg>"#,
+        );
+        assert!(hash_map.is_empty());
+        let errors = errors(r#"<svg viewBox="0 0 100 100"><rect width="10" height="-1"/><rect width="-5%" height="1"/></svg>"#);
+        assert!(matches!(&errors[..], [SvgError::Attribute { name: a, .. }, SvgError::Attribute { name: b, .. }] if a == "height" && b == "width"));
+    }
+
+    #[test]
+    fn circle_lengths() {
+        let hash_map = render(
+            r#"<svg viewBox="0 0 100 50">
+                <circle cx="50%" cy="50%" r="10%" stroke="red" fill="none"/>
+            </svg>"#,
+        );
+        let (points, ..) = &hash_map[&0];
+        let right = points.iter().map(|point| point.0).fold(f64::NEG_INFINITY, f64::max);
+        // a tenth of the 100 by 50 viewport's normalised diagonal, sqrt(6250)
+        assert!((right - 57.906).abs() < 0.01, "{right}");
+        let hash_map = render(r#"<svg viewBox="0 0 100 100"><circle cx="1in" cy="50" r="2px" stroke="red"/></svg>"#);
+        let right = hash_map[&0].0.iter().map(|point| point.0).fold(f64::NEG_INFINITY, f64::max);
+        assert!((right - 98.0).abs() < 0.01, "{right}");
+    }
+
+    #[test]
+    fn ellipses_flatten_to_the_tolerance() {
+        let svg = r#"<svg viewBox="0 0 100 50"><ellipse cx="50" cy="25" rx="25%" ry="1mm" stroke="red" fill="none"/></svg>"#;
+        let outline = |tolerance| {
+            let options = RenderOptions { tolerance, ..RenderOptions::default() };
+            render_with_options(svg, &options).0.remove(&0).unwrap().0
+        };
+        let (coarse, fine) = (outline(2.0), outline(0.05));
+        assert!(coarse.len() < fine.len());
+        // the radii are a quarter of the viewport's width and a millimetre
+        let extent = |points: &[(f64, f64, bool)], axis: fn(&(f64, f64, bool)) -> f64| {
+            points.iter().map(axis).fold(f64::NEG_INFINITY, f64::max) - points.iter().map(axis).fold(f64::INFINITY, f64::min)
+        };
+        assert!((extent(&fine, |point| point.0) - 50.0).abs() < 1e-9);
+        assert!((extent(&fine, |point| point.1) - 2.0 * 96.0 / 25.4 * 2.0).abs() < 1e-9);
+        assert_eq!(fine.first().map(|point| (point.0, point.1)), fine.last().map(|point| (point.0, point.1)));
+    }
+
+    #[test]
+    fn line_and_rect_lengths() {
+        let hash_map = render(
+            r#"<svg viewBox="0 0 200 100">
+                <line x1="10px" y1="0" x2="100%" y2="50%" stroke="red"/>
+                <rect x="5%" y="1in" width="10px" height="10%" stroke="red" fill="none"/>
+            </svg>"#,
+        );
+        let mut shapes = hash_map.into_values().map(|(points, ..)| points).collect::<Vec<_>>();
+        shapes.sort_by_key(|points| points.len());
+        // the view box is stretched to 100 canvas units, halving x
+        let line = shapes[0].iter().map(|point| (point.0, point.1)).collect::<Vec<_>>();
+        assert_eq!(line, vec![(5.0, 100.0), (100.0, 50.0)]);
+        let (left, right) = shapes[1].iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(left, right), point| (left.min(point.0), right.max(point.0)));
+        assert_eq!((left, right), (5.0, 10.0));
+        assert!(shapes[1].iter().any(|point| point.1 == 4.0));
+    }
+
+    #[test]
+    fn point_lists() {
+        assert_eq!(parse_numbers("10,20 30,40"), Some(vec![10.0, 20.0, 30.0, 40.0]));
+        assert_eq!(parse_numbers(" 10 20,\n\t30 , 40\n"), Some(vec![10.0, 20.0, 30.0, 40.0]));
+        assert_eq!(parse_numbers("10-20-.5.5"), Some(vec![10.0, -20.0, -0.5, 0.5]));
+        assert_eq!(parse_numbers("1e2,2E-1 3e+1"), Some(vec![100.0, 0.2, 30.0]));
+        assert_eq!(parse_numbers(""), Some(vec![]));
+        assert_eq!(parse_numbers("10 20 x"), None);
+        assert_eq!(parse_numbers("10px 20"), None);
+
+        // the odd coordinate at the end is left out
+        let hash_map = render(r#"<svg viewBox="0 0 100 100"><polyline points="0,0 10,0 10" stroke="red"/></svg>"#);
+        assert_eq!(hash_map[&0].0.len(), 2);
+        let errors = errors(r#"<svg viewBox="0 0 100 100"><polygon points="0,0 10,0 10;10"/></svg>"#);
+        assert!(matches!(&errors[..], [SvgError::Attribute { name, .. }] if name == "points"));
+    }
+
+    #[test]
+    fn recursive_references_stop() {
+        let hash_map = render(
+            r##"<svg viewBox="0 0 100 100">
+                <g id="a"><line x1="0" y1="0" x2="10" y2="0" stroke="red"/><use href="#a"/><use href="#b"/></g>
+                <g id="b"><use href="#a" y="10"/></g>
+            </svg>"##,
+        );
+        // g#a itself, its copy in g#b, and the copy inside the copy of g#b that g#a uses
+        assert_eq!(hash_map.len(), 3);
+    }
+
+    #[test]
+    fn references_that_fan_out_stop() {
+        // each level uses the one below ten times, 10^8 lines without a limit
+        let mut svg = String::from(r#"<svg viewBox="0 0 100 100"><defs><line id="l0" x2="10" stroke="red"/>"#);
+        for level in 1..=8 {
+            svg.push_str(&format!(r#"<g id="l{level}">"#));
+            for _ in 0..10 {
+                svg.push_str(&format!(r##"<use href="#l{}"/>"##, level - 1));
+            }
+            svg.push_str("</g>");
+        }
+        svg.push_str(r##"</defs><use href="#l8"/><rect width="10" height="10" stroke="blue"/></svg>"##);
+        let (mut hash_map, mut texts) = (HashMap::new(), Vec::new());
+        let layout = Rect::new(0, 0, 50, 25);
+        let (.., errors) = render_document(svg, &mut hash_map, &mut texts, layout, &RenderOptions::default()).unwrap();
+        assert!(matches!(errors.first(), Some(SvgError::Instances(MAX_INSTANCES))));
+        // the copies made before the limit are drawn, and so is the rest of the document
+        assert!(hash_map.len() <= MAX_INSTANCES + 2);
+        assert!(hash_map.values().any(|shape| shape.1.stroke_color() == Some(Color::Rgb(0, 0, 255))));
+    }
+
+    #[test]
+    fn stylesheets_cascade_between_attributes_and_style() {
+        let hash_map = render(
+            r#"<svg viewBox="0 0 100 100">
+                <line class="a" stroke="red" x1="0" y1="0" x2="10" y2="0"/>
+                <style><![CDATA[ .a { stroke: lime } g .a { stroke: blue } ]]></style>
+                <g><line class="a" x1="0" y1="0" x2="20" y2="0" style="stroke: #123"/></g>
+            </svg>"#,
+        );
+        let mut lines = hash_map.into_values().collect::<Vec<SvgPoints>>();
+        lines.sort_by(|a, b| a.0[1].0.total_cmp(&b.0[1].0));
+        assert_eq!(lines[0].1.stroke_color(), Some(Color::Rgb(0, 255, 0)));
+        assert_eq!(lines[1].1.stroke_color(), Some(Color::Rgb(0x11, 0x22, 0x33)));
     }
 }
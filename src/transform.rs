@@ -0,0 +1,191 @@
+use crate::error::SvgError;
+use crate::path::scan_number;
+
+/// A 2D affine transform, laid out like the SVG `matrix(a, b, c, d, e, f)`:
+/// x' = a * x + c * y + e and y' = b * x + d * y + f
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+impl Transform {
+    pub fn new(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Transform {
+        Transform { a, b, c, d, e, f }
+    }
+
+    pub fn identity() -> Transform {
+        Transform::new(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)
+    }
+
+    pub fn translate(tx: f64, ty: f64) -> Transform {
+        Transform::new(1.0, 0.0, 0.0, 1.0, tx, ty)
+    }
+
+    pub fn scale(sx: f64, sy: f64) -> Transform {
+        Transform::new(sx, 0.0, 0.0, sy, 0.0, 0.0)
+    }
+
+    /// rotate by `angle` degrees about the origin
+    pub fn rotate(angle: f64) -> Transform {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Transform::new(cos, sin, -sin, cos, 0.0, 0.0)
+    }
+
+    pub fn skew_x(angle: f64) -> Transform {
+        Transform::new(1.0, 0.0, angle.to_radians().tan(), 1.0, 0.0, 0.0)
+    }
+
+    pub fn skew_y(angle: f64) -> Transform {
+        Transform::new(1.0, angle.to_radians().tan(), 0.0, 1.0, 0.0, 0.0)
+    }
+
+    /// the transform that applies `other` first and then `self`
+    pub fn multiply(&self, other: &Transform) -> Transform {
+        Transform::new(
+            self.a * other.a + self.c * other.b,
+            self.b * other.a + self.d * other.b,
+            self.a * other.c + self.c * other.d,
+            self.b * other.c + self.d * other.d,
+            self.a * other.e + self.c * other.f + self.e,
+            self.b * other.e + self.d * other.f + self.f,
+        )
+    }
+
+    /// how much lengths grow on average, exact when the scale is uniform
+    pub fn mean_scale(&self) -> f64 {
+        (self.a * self.d - self.b * self.c).abs().sqrt()
+    }
+
+    /// the most any length can grow, the larger singular value of the matrix
+    pub fn max_scale(&self) -> f64 {
+        let sum = self.a.powi(2) + self.b.powi(2) + self.c.powi(2) + self.d.powi(2);
+        let det = self.a * self.d - self.b * self.c;
+        ((sum + (sum.powi(2) - 4.0 * det.powi(2)).max(0.0).sqrt()) / 2.0).sqrt()
+    }
+
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.a * x + self.c * y + self.e,
+            self.b * x + self.d * y + self.f,
+        )
+    }
+
+    /// parse a transform list such as `translate(10) rotate(30 50 50) scale(2)`.
+    /// the functions are composed left to right, so the last one is applied first
+    pub fn parse(value: &str) -> Result<Transform, SvgError> {
+        let invalid = || SvgError::attribute("transform", value);
+        let bytes = value.as_bytes();
+        let mut transform = Transform::identity();
+        let mut i = 0;
+        loop {
+            while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b',') {
+                i += 1;
+            }
+            if i == bytes.len() {
+                return Ok(transform);
+            }
+
+            let name_start = i;
+            while i < bytes.len() && bytes[i].is_ascii_alphabetic() {
+                i += 1;
+            }
+            let name = &value[name_start..i];
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            if i == bytes.len() || bytes[i] != b'(' {
+                return Err(invalid());
+            }
+            i += 1;
+
+            let mut args = Vec::new();
+            loop {
+                while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b',') {
+                    i += 1;
+                }
+                if i < bytes.len() && bytes[i] == b')' {
+                    i += 1;
+                    break;
+                }
+                let (number, next) = scan_number(bytes, i).ok_or_else(invalid)?;
+                args.push(number);
+                i = next;
+            }
+
+            let function = match (name, args.as_slice()) {
+                ("matrix", &[a, b, c, d, e, f]) => Transform::new(a, b, c, d, e, f),
+                ("translate", &[tx]) => Transform::translate(tx, 0.0),
+                ("translate", &[tx, ty]) => Transform::translate(tx, ty),
+                ("scale", &[s]) => Transform::scale(s, s),
+                ("scale", &[sx, sy]) => Transform::scale(sx, sy),
+                ("rotate", &[angle]) => Transform::rotate(angle),
+                // rotate about (cx, cy)
+                ("rotate", &[angle, cx, cy]) => Transform::translate(cx, cy)
+                    .multiply(&Transform::rotate(angle))
+                    .multiply(&Transform::translate(-cx, -cy)),
+                ("skewX", &[angle]) => Transform::skew_x(angle),
+                ("skewY", &[angle]) => Transform::skew_y(angle),
+                _ => return Err(invalid()),
+            };
+            transform = transform.multiply(&function);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_point(actual: (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-9 && (actual.1 - expected.1).abs() < 1e-9,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn single_functions() {
+        assert_point(Transform::parse("translate(10)").unwrap().apply(1.0, 2.0), (11.0, 2.0));
+        assert_point(Transform::parse("scale(2)").unwrap().apply(1.0, 2.0), (2.0, 4.0));
+        assert_point(Transform::parse("rotate(90)").unwrap().apply(1.0, 0.0), (0.0, 1.0));
+        assert_point(Transform::parse("rotate(180 50 50)").unwrap().apply(60.0, 50.0), (40.0, 50.0));
+        assert_point(Transform::parse("skewX(45)").unwrap().apply(0.0, 1.0), (1.0, 1.0));
+        assert_point(
+            Transform::parse("matrix(0,1,-1,0,5,6)").unwrap().apply(1.0, 2.0),
+            (3.0, 7.0),
+        );
+    }
+
+    #[test]
+    fn scales() {
+        let stretch = Transform::scale(4.0, 1.0).multiply(&Transform::rotate(30.0));
+        assert!((stretch.max_scale() - 4.0).abs() < 1e-9);
+        assert!((stretch.mean_scale() - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn lists_apply_right_to_left() {
+        let transform = Transform::parse("translate(10, 0) scale(2)").unwrap();
+        assert_point(transform.apply(1.0, 1.0), (12.0, 2.0));
+        let transform = Transform::parse(" scale(2)translate(10 0) ").unwrap();
+        assert_point(transform.apply(1.0, 1.0), (22.0, 2.0));
+    }
+
+    #[test]
+    fn invalid_lists() {
+        assert!(Transform::parse("rotate(1 2)").is_err());
+        assert!(Transform::parse("translate(1, 2").is_err());
+        assert!(Transform::parse("spin(90)").is_err());
+    }
+}